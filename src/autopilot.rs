@@ -0,0 +1,370 @@
+//! Players which are not people: before every step they look at the world and decide where the
+//! snake goes. They play the demo on the title screen, and many games quickly to test the game
+//! and to compare its settings.
+
+use std::collections::VecDeque;
+
+use crate::world::{Direction, Thing, Turn, World};
+
+/// Steers the first snake, the one of a single player, instead of the player
+pub trait Controller {
+    /// The turn to make before the next step; None keeps the snake going straight
+    fn decide(&mut self, world: &World) -> Option<Turn>;
+}
+
+/// The names of the strategies, for `strategy()`
+pub const STRATEGIES: [&str; 3] = ["greedy", "bfs", "hamiltonian"];
+
+/// The strategy with the given name, if there is one
+pub fn strategy(name: &str) -> Option<Box<dyn Controller>> {
+    match name {
+        "greedy" => Some(Box::new(Greedy)),
+        "bfs" => Some(Box::new(Pathfinder)),
+        "hamiltonian" => Some(Box::new(Hamiltonian::default())),
+        _ => None,
+    }
+}
+
+// what the snake can do in one step: go straight on, or turn once
+const MOVES: [Option<Turn>; 3] = [None, Some(Turn::Left), Some(Turn::Right)];
+
+/// Goes to the nearest food as the crow flies, unless it means a crash right away
+pub struct Greedy;
+
+impl Controller for Greedy {
+    fn decide(&mut self, w: &World) -> Option<Turn> {
+        let field = Field::new(w);
+        let food = food(w);
+        // straight on wins a tie
+        MOVES
+            .iter()
+            .filter_map(|turn| field.step(w, turn).map(|cell| (turn, cell)))
+            .min_by_key(|(_, cell)| {
+                food.iter()
+                    .map(|f| field.distance(*cell, *f))
+                    .min()
+                    .unwrap_or(0)
+            })
+            .and_then(|(turn, _)| turn.clone())
+    }
+}
+
+/// Goes to the nearest food along the shortest way around the obstacles, the walls and itself,
+/// if from there it can still get to its tail; a snake which can follow its tail cannot be
+/// trapped. Otherwise it follows the tail, and if it cannot, it goes where it has the most room.
+pub struct Pathfinder;
+
+impl Controller for Pathfinder {
+    fn decide(&mut self, w: &World) -> Option<Turn> {
+        let field = Field::new(w);
+        let moves: Vec<(usize, (u32, u32))> = MOVES
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, turn)| field.step(w, turn).map(|cell| (idx, cell)))
+            .collect();
+        let tail = w
+            .snake()
+            .cells
+            .last()
+            .expect("Programming error: a snake cannot be empty");
+        let tail = (tail.coords.x, tail.coords.y);
+        let near_tail = |cell| field.distance(cell, tail) <= 1;
+        let food = food(w);
+        let to_food = field
+            .first_move(&moves, |cell| food.contains(&cell))
+            .filter(|idx| field.first_move(&moves[*idx..=*idx], near_tail).is_some());
+        let best = to_food
+            .or_else(|| field.first_move(&moves, near_tail))
+            .or_else(|| {
+                (0..moves.len())
+                    .rev()
+                    .max_by_key(|idx| field.room(moves[*idx].1))
+            })?;
+        MOVES[moves[best].0].clone()
+    }
+}
+
+/// Follows a cycle through all the cells of the field. On an open field without obstacles the
+/// snake on the cycle cannot hit itself, however long it grows. Until it gets on the cycle,
+/// whenever an obstacle is on the next cell of the cycle, on the fields with walls, and on the
+/// fields with an odd number of both rows and columns, which have no such cycle, it plays as the
+/// `Pathfinder`, which does not bring it back to the cycle safely: a long snake which leaves the
+/// cycle may well trap itself.
+#[derive(Default)]
+pub struct Hamiltonian {
+    // the field size the cycle is for
+    size: (u32, u32),
+    // the next cell of every cell of the field; empty if there is no cycle
+    next: Vec<(u32, u32)>,
+}
+
+impl Controller for Hamiltonian {
+    fn decide(&mut self, w: &World) -> Option<Turn> {
+        // a new level may have a new size
+        if self.size != (w.width(), w.height()) {
+            self.size = (w.width(), w.height());
+            self.next = cycle(w.width(), w.height()).unwrap_or_default();
+        }
+        let walls = w.things.iter().any(|t| t.what == Thing::Wall);
+        if !self.next.is_empty() && !walls {
+            let field = Field::new(w);
+            let head = &w.snake().head().coords;
+            let next = self.next[field.index((head.x, head.y))];
+            if let Some(turn) = MOVES.iter().find(|turn| field.step(w, turn) == Some(next)) {
+                return turn.clone();
+            }
+        }
+        Pathfinder.decide(w)
+    }
+}
+
+/// The cycle through all the cells of the field, as the next cell of every cell by its index in
+/// `Field`. There is none if the numbers of both rows and columns are odd.
+fn cycle(width: u32, height: u32) -> Option<Vec<(u32, u32)>> {
+    if width < 2 || height < 2 {
+        return None;
+    }
+    let order: Vec<(u32, u32)> = if height.is_multiple_of(2) {
+        rows(width, height)
+    } else if width.is_multiple_of(2) {
+        // the same along the columns
+        rows(height, width)
+            .into_iter()
+            .map(|(y, x)| (x, y))
+            .collect()
+    } else {
+        return None;
+    };
+    let mut next = vec![(0, 0); (width * height) as usize];
+    for (idx, (x, y)) in order.iter().enumerate() {
+        next[((y - 1) * width + x - 1) as usize] = order[(idx + 1) % order.len()];
+    }
+    Some(next)
+}
+
+/// The cells of the field with an even number of rows in the order of the cycle: along the first
+/// row, back and forth along the other rows without the first column, then up the first column
+fn rows(width: u32, height: u32) -> Vec<(u32, u32)> {
+    let mut order: Vec<(u32, u32)> = (1..=width).map(|x| (x, 1)).collect();
+    for y in 2..=height {
+        if y.is_multiple_of(2) {
+            order.extend((2..=width).rev().map(|x| (x, y)));
+        } else {
+            order.extend((2..=width).map(|x| (x, y)));
+        }
+    }
+    order.extend((2..=height).rev().map(|y| (1, y)));
+    order
+}
+
+/// The food of all kinds on the field
+fn food(w: &World) -> Vec<(u32, u32)> {
+    w.things
+        .iter()
+        .filter(|t| {
+            matches!(
+                t.what,
+                Thing::Food | Thing::Fat | Thing::Lean | Thing::Mystery
+            )
+        })
+        .map(|t| (t.x, t.y))
+        .collect()
+}
+
+/// Where the snake cannot go on the next step
+struct Field {
+    width: u32,
+    height: u32,
+    wrap: bool,
+    // by the index of the cell
+    blocked: Vec<bool>,
+}
+
+impl Field {
+    fn new(w: &World) -> Self {
+        let (width, height) = (w.width(), w.height());
+        let mut field = Field {
+            width,
+            height,
+            wrap: w.config().wrap,
+            blocked: vec![false; (width * height) as usize],
+        };
+        for t in &w.things {
+            if matches!(t.what, Thing::Obstacle | Thing::Wall) {
+                let idx = field.index((t.x, t.y));
+                field.blocked[idx] = true;
+            }
+        }
+        for snake in &w.snakes {
+            // the tail moves away, unless the snake grows or is out of the game
+            let body = if snake.growing() || snake.dead.is_some() {
+                &snake.cells[..]
+            } else {
+                &snake.cells[..snake.cells.len() - 1]
+            };
+            for s in body {
+                let idx = field.index((s.coords.x, s.coords.y));
+                field.blocked[idx] = true;
+            }
+        }
+        field
+    }
+
+    // coordinates are from 1 to the field width and height
+    fn index(&self, (x, y): (u32, u32)) -> usize {
+        ((y - 1) * self.width + x - 1) as usize
+    }
+
+    /// The cell next to the given one, across the border if the field wraps
+    fn next(&self, (x, y): (u32, u32), dir: &Direction) -> Option<(u32, u32)> {
+        let cell = match dir {
+            Direction::Up if y > 1 => (x, y - 1),
+            Direction::Up if self.wrap => (x, self.height),
+            Direction::Down if y < self.height => (x, y + 1),
+            Direction::Down if self.wrap => (x, 1),
+            Direction::Left if x > 1 => (x - 1, y),
+            Direction::Left if self.wrap => (self.width, y),
+            Direction::Right if x < self.width => (x + 1, y),
+            Direction::Right if self.wrap => (1, y),
+            _ => return None,
+        };
+        Some(cell)
+    }
+
+    /// Where the head gets after the turn (or without it) if the snake survives the step
+    fn step(&self, w: &World, turn: &Option<Turn>) -> Option<(u32, u32)> {
+        let head = w.snake().head();
+        let dir = match turn {
+            Some(turn) => head.dir.turned(turn),
+            None => head.dir.clone(),
+        };
+        self.next((head.coords.x, head.coords.y), &dir)
+            .filter(|cell| !self.blocked[self.index(*cell)])
+    }
+
+    /// The number of steps between the cells, ignoring what is on the way
+    fn distance(&self, (ax, ay): (u32, u32), (bx, by): (u32, u32)) -> u32 {
+        let along = |a: u32, b: u32, size: u32| {
+            let d = a.abs_diff(b);
+            if self.wrap {
+                d.min(size - d)
+            } else {
+                d
+            }
+        };
+        along(ax, bx, self.width) + along(ay, by, self.height)
+    }
+
+    /// The free cells around, e.g. in the four directions, which are not marked yet
+    fn neighbours<'a>(
+        &'a self,
+        cell: (u32, u32),
+        seen: &'a [bool],
+    ) -> impl Iterator<Item = (u32, u32)> + 'a {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+        .iter()
+        .filter_map(move |dir| self.next(cell, dir))
+        .filter(|next| !self.blocked[self.index(*next)] && !seen[self.index(*next)])
+    }
+
+    /// How many free cells there are where the snake can get from the cell, the cell included
+    fn room(&self, from: (u32, u32)) -> usize {
+        let mut seen = vec![false; self.blocked.len()];
+        seen[self.index(from)] = true;
+        let mut todo = vec![from];
+        let mut count = 0;
+        while let Some(cell) = todo.pop() {
+            count += 1;
+            let next: Vec<(u32, u32)> = self.neighbours(cell, &seen).collect();
+            for n in next {
+                seen[self.index(n)] = true;
+                todo.push(n);
+            }
+        }
+        count
+    }
+
+    /// The index in `moves` of the move which starts the shortest way to a cell the target
+    /// accepts, if there is a way; the moves are given with the cells they lead to
+    fn first_move(
+        &self,
+        moves: &[(usize, (u32, u32))],
+        target: impl Fn((u32, u32)) -> bool,
+    ) -> Option<usize> {
+        let mut seen = vec![false; self.blocked.len()];
+        let mut todo = VecDeque::new();
+        for (idx, (_, cell)) in moves.iter().enumerate() {
+            seen[self.index(*cell)] = true;
+            todo.push_back((*cell, idx));
+        }
+        while let Some((cell, first)) = todo.pop_front() {
+            if target(cell) {
+                return Some(first);
+            }
+            let next: Vec<(u32, u32)> = self.neighbours(cell, &seen).collect();
+            for n in next {
+                seen[self.index(n)] = true;
+                todo.push_back((n, first));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::world::test_world;
+
+    fn play(controller: &mut dyn Controller, w: &mut World, steps: u32) {
+        for _ in 0..steps {
+            if let Some(turn) = controller.decide(w) {
+                w.turn(&turn);
+            }
+            assert!(w.step().is_ok(), "the snake should survive the step");
+        }
+    }
+
+    #[test]
+    fn cycle_goes_through_every_cell() {
+        for (width, height) in [(4, 3), (5, 4), (6, 6), (2, 7)] {
+            let next = cycle(width, height).expect("there should be a cycle");
+            let mut seen = vec![false; next.len()];
+            let mut cell = (1, 1);
+            for _ in 0..next.len() {
+                let idx = ((cell.1 - 1) * width + cell.0 - 1) as usize;
+                assert!(!seen[idx], "{:?} is visited twice", cell);
+                seen[idx] = true;
+                let n = next[idx];
+                assert_eq!(cell.0.abs_diff(n.0) + cell.1.abs_diff(n.1), 1);
+                cell = n;
+            }
+            assert_eq!(cell, (1, 1));
+        }
+        assert!(cycle(5, 5).is_none());
+    }
+
+    #[test]
+    fn pathfinder_eats() {
+        let mut w = test_world(&Level::open(20, 20, None), 1);
+        play(&mut Pathfinder, &mut w, 500);
+        assert!(w.score() >= 5, "the score is only {}", w.score());
+    }
+
+    #[test]
+    fn hamiltonian_survives_a_long_snake() {
+        let mut w = test_world(&Level::open(12, 12, None), 3);
+        play(&mut Hamiltonian::default(), &mut w, 1000);
+        assert!(
+            w.snake().cells.len() > 30,
+            "the snake is only {} long",
+            w.snake().cells.len()
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Many games played by a computer player without a frontend, and what happened in them, so
+//! that the probabilities in the configuration can be tuned by numbers instead of by feel.
+
+use crate::autopilot::Controller;
+use crate::config::GameConfig;
+use crate::game::Game;
+use crate::input::Steer;
+use crate::level::Level;
+use crate::world::{Spawned, StepError};
+
+/// The ways a game ends, by their names in the statistics; "none" is a game stopped after the
+/// maximum number of steps
+pub const DEATHS: [&str; 7] = [
+    "obstacle",
+    "out_of_field",
+    "self_hit",
+    "wall",
+    "snake",
+    "head_on",
+    "none",
+];
+
+/// How one game went
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameStats {
+    pub seed: u64,
+    pub score: u32,
+    // the number of steps made
+    pub ticks: u64,
+    pub length: usize,
+    // the last level played, from 0
+    pub level: usize,
+    // None if the game was stopped
+    pub death: Option<StepError>,
+    pub spawned: Spawned,
+}
+
+/// Play a game from the first of the levels until the snake dies, but at most `max_ticks` steps.
+/// A single open level keeps the game on the field of the configuration; the campaign goes on to
+/// the designed levels, which have their own sizes and walls.
+pub fn play(
+    config: &GameConfig,
+    levels: &[Level],
+    controller: &mut dyn Controller,
+    seed: u64,
+    max_ticks: u64,
+) -> GameStats {
+    let mut game = Game::new(config, levels, 0, seed);
+    let mut death = None;
+    while game.world().tick() < max_ticks {
+        if let Some(turn) = controller.decide(game.world()) {
+            game.steer(0, Steer::Turn(turn));
+        }
+        let step = game.step();
+        if game.over() {
+            death = step.results.iter().find_map(|r| r.as_ref().err()).copied();
+            break;
+        }
+    }
+    let w = game.world();
+    GameStats {
+        seed,
+        score: w.score(),
+        ticks: w.tick(),
+        length: w.snake().cells.len(),
+        level: game.level_index(),
+        death,
+        spawned: w.spawned().clone(),
+    }
+}
+
+/// The name of the way the game ended, one of `DEATHS`
+pub fn death_name(death: Option<StepError>) -> &'static str {
+    DEATHS[death_index(death)]
+}
+
+fn death_index(death: Option<StepError>) -> usize {
+    match death {
+        Some(StepError::Obstacle) => 0,
+        Some(StepError::OutOfField) => 1,
+        Some(StepError::SelfHit) => 2,
+        Some(StepError::Wall) => 3,
+        Some(StepError::Snake) => 4,
+        Some(StepError::HeadOn) => 5,
+        None => 6,
+    }
+}
+
+/// The smallest, the largest, the average and the quartiles of some numbers
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    pub min: u64,
+    pub q1: u64,
+    pub median: u64,
+    pub q3: u64,
+    pub max: u64,
+    pub mean: f64,
+}
+
+impl Distribution {
+    pub fn new(mut values: Vec<u64>) -> Self {
+        if values.is_empty() {
+            return Distribution {
+                min: 0,
+                q1: 0,
+                median: 0,
+                q3: 0,
+                max: 0,
+                mean: 0.0,
+            };
+        }
+        values.sort_unstable();
+        // the smallest value which is not less than the given part of the values
+        let rank = |part: f64| values[((values.len() as f64 * part).ceil() as usize).max(1) - 1];
+        Distribution {
+            min: values[0],
+            q1: rank(0.25),
+            median: rank(0.5),
+            q3: rank(0.75),
+            max: values[values.len() - 1],
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+        }
+    }
+}
+
+/// What happened in all the games together
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub games: usize,
+    pub score: Distribution,
+    pub ticks: Distribution,
+    // the number of games which ended in every way, in the order of `DEATHS`
+    pub deaths: [usize; DEATHS.len()],
+    // the things which appeared in all the games
+    pub spawned: Spawned,
+}
+
+impl Summary {
+    pub fn new(games: &[GameStats]) -> Self {
+        let mut deaths = [0; DEATHS.len()];
+        let mut spawned = Spawned::default();
+        for g in games {
+            deaths[death_index(g.death)] += 1;
+            spawned.food += g.spawned.food;
+            spawned.lean += g.spawned.lean;
+            spawned.fat += g.spawned.fat;
+            spawned.obstacle += g.spawned.obstacle;
+            spawned.mystery += g.spawned.mystery;
+        }
+        Summary {
+            games: games.len(),
+            score: Distribution::new(games.iter().map(|g| g.score as u64).collect()),
+            ticks: Distribution::new(games.iter().map(|g| g.ticks).collect()),
+            deaths,
+            spawned,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::Pathfinder;
+    use crate::level;
+
+    #[test]
+    fn the_same_seed_plays_the_same_game() {
+        let config = GameConfig {
+            field_height: 16,
+            ..GameConfig::default()
+        };
+        let levels = level::campaign(&config);
+        let first = play(&config, &levels, &mut Pathfinder, 7, 2000);
+        let second = play(&config, &levels, &mut Pathfinder, 7, 2000);
+        assert_eq!(first, second);
+        // the first food is there before the first step
+        assert!(first.spawned.food > 0);
+    }
+
+    #[test]
+    fn quartiles() {
+        let d = Distribution::new(vec![5, 1, 4, 2, 3, 6, 8, 7]);
+        assert_eq!((d.min, d.q1, d.median, d.q3, d.max), (1, 2, 4, 6, 8));
+        assert_eq!(d.mean, 4.5);
+        assert_eq!(Distribution::new(vec![]).max, 0);
+    }
+}
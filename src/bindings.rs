@@ -0,0 +1,392 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use sdl2::controller::Button;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+
+use rnake::input::Steer;
+use rnake::world::{Direction, Turn};
+
+/// What a key does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    TurnLeft,
+    TurnRight,
+    Pause,
+    Quit,
+    Confirm,
+    Fullscreen,
+    Screenshot,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Pause,
+        Action::Quit,
+        Action::Confirm,
+        Action::Fullscreen,
+        Action::Screenshot,
+    ];
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Action::TurnLeft => "Turn left",
+            Action::TurnRight => "Turn right",
+            Action::Pause => "Pause",
+            Action::Quit => "Quit",
+            Action::Confirm => "Confirm",
+            Action::Fullscreen => "Full screen",
+            Action::Screenshot => "Screenshot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How the keys steer the snake
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlMode {
+    // the turn keys turn the snake left and right of where its head looks
+    Relative,
+    // the arrow keys point the snake head up, down, left and right on the screen
+    Absolute,
+}
+
+impl ControlMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ControlMode::Relative => "relative",
+            ControlMode::Absolute => "absolute",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            ControlMode::Relative => ControlMode::Absolute,
+            ControlMode::Absolute => ControlMode::Relative,
+        }
+    }
+}
+
+/// The keys for all the actions.
+///
+/// In the absolute mode the arrow keys always steer the snake. The bindings are saved in the
+/// user's configuration directory as `rnake/bindings.toml`, with the keys written by their SDL
+/// names, e.g. `turn_left = "Left"`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Bindings {
+    pub control: ControlMode,
+    // the turn keys are used only in the relative mode
+    #[serde(with = "key_name")]
+    pub turn_left: Keycode,
+    #[serde(with = "key_name")]
+    pub turn_right: Keycode,
+    #[serde(with = "key_name")]
+    pub pause: Keycode,
+    #[serde(with = "key_name")]
+    pub quit: Keycode,
+    #[serde(with = "key_name")]
+    pub confirm: Keycode,
+    // switch between the full screen and the window
+    #[serde(with = "key_name")]
+    pub fullscreen: Keycode,
+    // save what is on the screen to a PNG file
+    #[serde(with = "key_name")]
+    pub screenshot: Keycode,
+}
+
+/// The keys of the game before they could be changed: the inverted ones
+impl Default for Bindings {
+    fn default() -> Self {
+        Self::inverted()
+    }
+}
+
+impl Bindings {
+    /// The keys of the first version of the game, kept as the default; the README calls them
+    /// inverted, as the author meant the arrows the other way round
+    pub fn inverted() -> Self {
+        Bindings {
+            control: ControlMode::Relative,
+            turn_left: Keycode::Left,
+            turn_right: Keycode::Right,
+            pause: Keycode::P,
+            quit: Keycode::Escape,
+            confirm: Keycode::Space,
+            fullscreen: Keycode::F11,
+            screenshot: Keycode::F12,
+        }
+    }
+
+    /// The inverted keys with the two arrows swapped
+    pub fn classic() -> Self {
+        Bindings {
+            turn_left: Keycode::Right,
+            turn_right: Keycode::Left,
+            ..Self::inverted()
+        }
+    }
+
+    /// The name of the built-in profile the keys are, if any; the control mode does not matter
+    pub fn profile(&self) -> Option<&'static str> {
+        let keys = Bindings {
+            control: ControlMode::Relative,
+            ..self.clone()
+        };
+        if keys == Self::classic() {
+            Some("classic")
+        } else if keys == Self::inverted() {
+            Some("inverted")
+        } else {
+            None
+        }
+    }
+
+    /// The keys of the next built-in profile; custom keys are followed by the first one
+    pub fn next_profile(&self) -> Self {
+        let next = match self.profile() {
+            Some("classic") => Self::inverted(),
+            _ => Self::classic(),
+        };
+        Bindings {
+            control: self.control,
+            ..next
+        }
+    }
+
+    pub fn key(&self, action: Action) -> Keycode {
+        match action {
+            Action::TurnLeft => self.turn_left,
+            Action::TurnRight => self.turn_right,
+            Action::Pause => self.pause,
+            Action::Quit => self.quit,
+            Action::Confirm => self.confirm,
+            Action::Fullscreen => self.fullscreen,
+            Action::Screenshot => self.screenshot,
+        }
+    }
+
+    /// Bind the key to the action. The action which had the key before gets the old key of this
+    /// one, so that a key never does two things.
+    pub fn set_key(&mut self, action: Action, key: Keycode) {
+        let old = self.key(action);
+        if let Some(other) = Action::ALL
+            .into_iter()
+            .find(|other| *other != action && self.key(*other) == key)
+        {
+            self.bind(other, old);
+        }
+        self.bind(action, key);
+    }
+
+    fn bind(&mut self, action: Action, key: Keycode) {
+        match action {
+            Action::TurnLeft => self.turn_left = key,
+            Action::TurnRight => self.turn_right = key,
+            Action::Pause => self.pause = key,
+            Action::Quit => self.quit = key,
+            Action::Confirm => self.confirm = key,
+            Action::Fullscreen => self.fullscreen = key,
+            Action::Screenshot => self.screenshot = key,
+        }
+    }
+
+    /// The action of the game controller button, if any. The buttons are not configurable: the
+    /// shoulder buttons (and the D-pad in the relative mode) turn the snake, Start pauses the
+    /// game and Back quits it.
+    pub fn button_action(&self, button: Button) -> Option<Action> {
+        let relative = self.control == ControlMode::Relative;
+        match button {
+            Button::LeftShoulder => Some(Action::TurnLeft),
+            Button::RightShoulder => Some(Action::TurnRight),
+            Button::DPadLeft if relative => Some(Action::TurnLeft),
+            Button::DPadRight if relative => Some(Action::TurnRight),
+            Button::Start => Some(Action::Pause),
+            Button::A => Some(Action::Confirm),
+            Button::Back => Some(Action::Quit),
+            _ => None,
+        }
+    }
+
+    /// Where the game controller button points the snake head in the absolute mode, if anywhere
+    pub fn button_direction(&self, button: Button) -> Option<Direction> {
+        if self.control != ControlMode::Absolute {
+            return None;
+        }
+        match button {
+            Button::DPadUp => Some(Direction::Up),
+            Button::DPadDown => Some(Direction::Down),
+            Button::DPadLeft => Some(Direction::Left),
+            Button::DPadRight => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Whether the event is a key or a button which confirms, e.g. starts a new game; on a game
+    /// controller Start does it as well as A
+    pub fn confirms(&self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => self.action(*keycode) == Some(Action::Confirm),
+            Event::ControllerButtonDown { button, .. } => {
+                matches!(button, Button::A | Button::Start)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the event is a key or a button which quits
+    pub fn quits(&self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => self.action(*keycode) == Some(Action::Quit),
+            Event::ControllerButtonDown { button, .. } => {
+                self.button_action(*button) == Some(Action::Quit)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the event is the key which takes a screenshot
+    pub fn screenshots(&self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => self.action(*keycode) == Some(Action::Screenshot),
+            _ => false,
+        }
+    }
+
+    /// Where the key points the snake head in the absolute mode, if anywhere
+    pub fn direction(&self, key: Keycode) -> Option<Direction> {
+        if self.control != ControlMode::Absolute {
+            return None;
+        }
+        match key {
+            Keycode::Up => Some(Direction::Up),
+            Keycode::Down => Some(Direction::Down),
+            Keycode::Left => Some(Direction::Left),
+            Keycode::Right => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// How the key steers the second player's snake in a versus game, if it does. The keys are
+    /// fixed: A and D turn the snake, or in the absolute mode W, A, S and D point it up, left,
+    /// down and right.
+    pub fn second_player(&self, key: Keycode) -> Option<Steer> {
+        match (self.control, key) {
+            (ControlMode::Relative, Keycode::A) => Some(Steer::Turn(Turn::Left)),
+            (ControlMode::Relative, Keycode::D) => Some(Steer::Turn(Turn::Right)),
+            (ControlMode::Absolute, Keycode::W) => Some(Steer::Direction(Direction::Up)),
+            (ControlMode::Absolute, Keycode::A) => Some(Steer::Direction(Direction::Left)),
+            (ControlMode::Absolute, Keycode::S) => Some(Steer::Direction(Direction::Down)),
+            (ControlMode::Absolute, Keycode::D) => Some(Steer::Direction(Direction::Right)),
+            _ => None,
+        }
+    }
+
+    /// The action bound to the key, if any
+    pub fn action(&self, key: Keycode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.key(*action) == key)
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rnake").join("bindings.toml"))
+    }
+
+    /// Read the saved bindings; without the file (or with a broken one) the inverted profile is
+    /// used.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|e| {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            eprintln!("Cannot save key bindings: no configuration directory");
+            return;
+        };
+        let text = toml::to_string(self).expect("Should be able to serialize key bindings");
+        let saved = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(&path, text)),
+            None => fs::write(&path, text),
+        };
+        if let Err(e) = saved {
+            eprintln!("Cannot save key bindings to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Keys are written by their SDL names
+mod key_name {
+    use sdl2::keyboard::Keycode;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &Keycode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Keycode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Keycode::from_name(&name).ok_or_else(|| D::Error::custom(format!("unknown key '{}'", name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_keys_are_the_inverted_ones() {
+        let bindings = Bindings::default();
+        assert_eq!(bindings.control, ControlMode::Relative);
+        assert_eq!(bindings.profile(), Some("inverted"));
+        assert_eq!(bindings.action(Keycode::Right), Some(Action::TurnRight));
+        assert_eq!(bindings.action(Keycode::Left), Some(Action::TurnLeft));
+    }
+
+    #[test]
+    fn a_key_taken_by_another_action_is_swapped() {
+        let mut bindings = Bindings::classic();
+        bindings.set_key(Action::Pause, Keycode::Escape);
+        assert_eq!(bindings.pause, Keycode::Escape);
+        assert_eq!(bindings.quit, Keycode::P);
+        // the same key again changes nothing
+        bindings.set_key(Action::Pause, Keycode::Escape);
+        assert_eq!(
+            (bindings.pause, bindings.quit),
+            (Keycode::Escape, Keycode::P)
+        );
+        bindings.set_key(Action::Screenshot, Keycode::S);
+        assert_eq!(bindings.screenshot, Keycode::S);
+        let keys: Vec<Keycode> = Action::ALL.iter().map(|a| bindings.key(*a)).collect();
+        for (idx, key) in keys.iter().enumerate() {
+            assert!(!keys[idx + 1..].contains(key), "{} is bound twice", key);
+        }
+    }
+}
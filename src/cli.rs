@@ -0,0 +1,120 @@
+//! Command line parsing shared by the binaries: the options of the game configuration, which
+//! they all have, and the values of the options. Every binary parses its own options.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::config::{ConfigError, GameConfig};
+
+/// The options of the game configuration
+#[derive(Default)]
+pub struct ConfigOptions {
+    // game configuration file
+    pub config: Option<PathBuf>,
+    // `key=value` overrides of the game configuration
+    pub overrides: Vec<String>,
+}
+
+impl ConfigOptions {
+    /// Take `--config` or `--set`; false for any other option
+    pub fn option(&mut self, name: &str, args: &mut Args) -> Result<bool, String> {
+        match name {
+            "--config" => self.config = Some(PathBuf::from(args.value()?)),
+            "--set" => self.overrides.push(args.value()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub fn load(&self) -> Result<GameConfig, ConfigError> {
+        GameConfig::load(self.config.as_deref(), &self.overrides)
+    }
+}
+
+/// The arguments after the option being parsed
+pub struct Args {
+    args: Box<dyn Iterator<Item = String>>,
+    // the option being parsed, for the errors
+    option: String,
+}
+
+impl Args {
+    /// The next argument as the value of the option
+    pub fn value(&mut self) -> Result<String, String> {
+        self.args
+            .next()
+            .ok_or_else(|| format!("Option '{}' needs a value", self.option))
+    }
+
+    /// The value of the option as a number or the like; `what` names it in the error
+    pub fn parsed<T: FromStr>(&mut self, what: &str) -> Result<T, String> {
+        let v = self.value()?;
+        v.parse().map_err(|_| format!("Bad {} '{}'", what, v))
+    }
+}
+
+/// Go through the options; `option` takes the values of an option from the arguments and returns
+/// false for an option it does not know
+pub fn parse(
+    args: impl Iterator<Item = String> + 'static,
+    mut option: impl FnMut(&str, &mut Args) -> Result<bool, String>,
+) -> Result<(), String> {
+    let mut args = Args {
+        args: Box::new(args),
+        option: String::new(),
+    };
+    while let Some(name) = args.args.next() {
+        args.option = name;
+        let name = args.option.clone();
+        if !option(&name, &mut args)? {
+            return Err(format!("Unknown option '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> {
+        line.split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn own_and_config_options() {
+        let mut config = ConfigOptions::default();
+        let mut games = 0;
+        parse(
+            args("--set wrap=true --games 3 --config a.toml"),
+            |name, args| {
+                match name {
+                    "--games" => games = args.parsed("number of games")?,
+                    _ => return config.option(name, args),
+                }
+                Ok(true)
+            },
+        )
+        .expect("Should be able to parse the options");
+        assert_eq!(games, 3);
+        assert_eq!(config.config, Some(PathBuf::from("a.toml")));
+        assert_eq!(config.overrides, ["wrap=true"]);
+    }
+
+    #[test]
+    fn bad_options() {
+        let mut config = ConfigOptions::default();
+        let mut parse_line = |line| parse(args(line), |name, args| config.option(name, args));
+        assert_eq!(
+            parse_line("--seed 3"),
+            Err("Unknown option '--seed'".to_string())
+        );
+        assert_eq!(
+            parse_line("--set"),
+            Err("Option '--set' needs a value".to_string())
+        );
+    }
+}
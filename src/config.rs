@@ -0,0 +1,413 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+// the initial snake is 3 cells long, and new things never appear next to the snake head,
+// so smaller fields either do not fit the snake or have no room for food
+pub const MIN_FIELD_SIZE: u32 = 8;
+// the number of cells of the field must fit in a u32, with room to spare
+pub const MAX_FIELD_SIZE: u32 = 1000;
+// the slowest game: ten seconds between two steps of the snake
+pub const MAX_FRAME_DELTA: u64 = 10_000;
+// more turns ahead than this cannot be made by hand anyway
+pub const MAX_TURN_QUEUE: usize = 100;
+// the most a snake can grow from one thing it eats, and the most a mystery can score, so that the
+// lengths and the scores stay far from the u32 limit
+pub const MAX_GROW_SNAKE: u32 = 1000;
+pub const MAX_MYSTERY_SCORE: u32 = 1000;
+// the smallest window the game can be played in
+pub const MIN_WINDOW_SIZE: (u32, u32) = (320, 240);
+
+/// All the tunable values of the game.
+///
+/// Every field has a default, so a configuration file needs to list only what it changes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    // the field is field_width x field_height cells; without the width the frontend makes
+    // the field as wide as the screen allows with square cells
+    pub field_width: Option<u32>,
+    pub field_height: u32,
+    // play in a window of this size instead of the full screen
+    pub window: Option<(u32, u32)>,
+    // the field has no wall around it, and the snake leaving it comes back on the other side
+    pub wrap: bool,
+    // milliseconds between two steps of the snake
+    pub frame_delta: u64,
+    // how many turns the player can make ahead; the snake makes one turn per step
+    pub turn_queue: usize,
+    pub food_lifetime: u32,
+    pub food_grow_snake: u32,
+    pub obstacle_lifetime: u32,
+    pub obstacle_p: f64,
+    pub mystery_p: f64,
+    pub mystery_lifetime: u32,
+    pub mystery_score: u32,
+    pub mystery_grow_snake: u32,
+    pub lean_p: f64,
+    pub lean_after_food: u32,
+    pub lean_lifetime: u32,
+    pub fat_p: f64,
+    pub fat_grow_snake: u32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            field_width: None,
+            field_height: 30,
+            window: None,
+            wrap: false,
+            frame_delta: 60,
+            turn_queue: 3,
+            food_lifetime: 60,
+            food_grow_snake: 3,
+            obstacle_lifetime: 60,
+            obstacle_p: 0.015,
+            mystery_p: 0.0025,
+            mystery_lifetime: 120,
+            mystery_score: 5,
+            mystery_grow_snake: 15,
+            lean_p: 0.5,
+            lean_after_food: 5,
+            lean_lifetime: 60,
+            fat_p: 0.1,
+            fat_grow_snake: 6,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            ConfigError::Parse(msg) => write!(f, "bad configuration: {}", msg),
+            ConfigError::Invalid(msg) => write!(f, "invalid configuration: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl GameConfig {
+    /// Read the configuration from the given TOML file (if any), then apply the overrides.
+    ///
+    /// Every override is `key=value`, where the value is written as in the file; a value
+    /// which is not valid TOML is taken as a string.
+    pub fn load(path: Option<&Path>, overrides: &[String]) -> Result<Self, ConfigError> {
+        let mut table = match path {
+            Some(path) => {
+                let text =
+                    fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_owned(), e))?;
+                text.parse::<toml::Table>()
+                    .map_err(|e| ConfigError::Parse(format!("{}: {}", path.display(), e)))?
+            }
+            None => toml::Table::new(),
+        };
+        for o in overrides {
+            let (key, value) = o
+                .split_once('=')
+                .ok_or_else(|| ConfigError::Parse(format!("'{}' is not key=value", o)))?;
+            let value = format!("value = {}", value.trim())
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut t| t.remove("value"))
+                .unwrap_or_else(|| toml::Value::String(value.trim().to_string()));
+            table.insert(key.trim().to_string(), value);
+        }
+        Self::from_table(table)
+    }
+
+    pub fn from_table(table: toml::Table) -> Result<Self, ConfigError> {
+        let config: GameConfig = table.try_into().map_err(|e: toml::de::Error| {
+            ConfigError::Parse(e.to_string().trim_end().replace('\n', " "))
+        })?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        for (name, size) in [
+            ("field_width", self.width()),
+            ("field_height", self.field_height),
+        ] {
+            if size < MIN_FIELD_SIZE {
+                return Err(ConfigError::Invalid(format!(
+                    "{} is {}, but it must be at least {} to fit the snake and the food",
+                    name, size, MIN_FIELD_SIZE
+                )));
+            }
+            if size > MAX_FIELD_SIZE {
+                return Err(ConfigError::Invalid(format!(
+                    "{} is {}, but it must be at most {}",
+                    name, size, MAX_FIELD_SIZE
+                )));
+            }
+        }
+        if let Some((width, height)) = self.window {
+            let (min_width, min_height) = MIN_WINDOW_SIZE;
+            if width < min_width || height < min_height {
+                return Err(ConfigError::Invalid(format!(
+                    "the window is {}x{}, but it must be at least {}x{}",
+                    width, height, min_width, min_height
+                )));
+            }
+        }
+        if !(1..=MAX_FRAME_DELTA).contains(&self.frame_delta) {
+            return Err(ConfigError::Invalid(format!(
+                "frame_delta is {}, but it must be between 1 and {}",
+                self.frame_delta, MAX_FRAME_DELTA
+            )));
+        }
+        if !(1..=MAX_TURN_QUEUE).contains(&self.turn_queue) {
+            return Err(ConfigError::Invalid(format!(
+                "turn_queue is {}, but it must be between 1 and {}",
+                self.turn_queue, MAX_TURN_QUEUE
+            )));
+        }
+        for (name, value, max) in [
+            ("food_grow_snake", self.food_grow_snake, MAX_GROW_SNAKE),
+            ("fat_grow_snake", self.fat_grow_snake, MAX_GROW_SNAKE),
+            (
+                "mystery_grow_snake",
+                self.mystery_grow_snake,
+                MAX_GROW_SNAKE,
+            ),
+            ("mystery_score", self.mystery_score, MAX_MYSTERY_SCORE),
+        ] {
+            if value > max {
+                return Err(ConfigError::Invalid(format!(
+                    "{} is {}, but it must be at most {}",
+                    name, value, max
+                )));
+            }
+        }
+        for (name, p) in [
+            ("obstacle_p", self.obstacle_p),
+            ("mystery_p", self.mystery_p),
+            ("lean_p", self.lean_p),
+            ("fat_p", self.fat_p),
+        ] {
+            if !(0.0..=1.0).contains(&p) {
+                return Err(ConfigError::Invalid(format!(
+                    "{} is {}, but a probability must be between 0 and 1",
+                    name, p
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The width of the field; a field without the width set is square.
+    pub fn width(&self) -> u32 {
+        self.field_width.unwrap_or(self.field_height)
+    }
+
+    /// If the width of the field is not set, make the field as wide as possible with square
+    /// cells on a screen of the given size, keeping the height. The wall around the field takes
+    /// one cell on each side.
+    pub fn fit_width(&mut self, screen_width: u32, screen_height: u32) {
+        if self.field_width.is_some() {
+            return;
+        }
+        let cell = screen_height / (self.field_height + 2);
+        if let Some(cells) = screen_width.checked_div(cell) {
+            self.field_width = Some(
+                cells
+                    .saturating_sub(2)
+                    .clamp(MIN_FIELD_SIZE, MAX_FIELD_SIZE),
+            );
+        }
+    }
+
+    /// The configuration as TOML, one `key = value` per line
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Should be able to serialize the configuration")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(config: GameConfig) -> String {
+        match config.validate() {
+            Err(ConfigError::Invalid(msg)) => msg,
+            other => panic!("expected an invalid configuration, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn probabilities_are_between_0_and_1() {
+        let msg = invalid(GameConfig {
+            obstacle_p: 1.5,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("obstacle_p is 1.5"), "{}", msg);
+        let msg = invalid(GameConfig {
+            fat_p: -0.1,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("fat_p is -0.1"), "{}", msg);
+        assert!(GameConfig {
+            mystery_p: 1.0,
+            lean_p: 0.0,
+            ..GameConfig::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn field_size_limits() {
+        let msg = invalid(GameConfig {
+            field_height: MIN_FIELD_SIZE - 1,
+            field_width: Some(20),
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("field_height is 7"), "{}", msg);
+        // without the width the field is square, so the width is too small as well
+        let msg = invalid(GameConfig {
+            field_height: 3,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("field_width is 3"), "{}", msg);
+        let msg = invalid(GameConfig {
+            field_height: 70000,
+            field_width: Some(20),
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("field_height is 70000"), "{}", msg);
+        assert!(GameConfig {
+            field_height: MAX_FIELD_SIZE,
+            field_width: Some(MIN_FIELD_SIZE),
+            ..GameConfig::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn frame_delta_and_turn_queue_limits() {
+        let msg = invalid(GameConfig {
+            frame_delta: 0,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("frame_delta is 0"), "{}", msg);
+        let msg = invalid(GameConfig {
+            frame_delta: u64::MAX,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("frame_delta is"), "{}", msg);
+        let result = GameConfig::load(None, &["turn_queue=1000000000000".to_string()]);
+        assert!(matches!(
+            result,
+            Err(ConfigError::Invalid(msg)) if msg.starts_with("turn_queue is 1000000000000")
+        ));
+        assert!(GameConfig {
+            frame_delta: MAX_FRAME_DELTA,
+            turn_queue: MAX_TURN_QUEUE,
+            ..GameConfig::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn growth_and_score_limits() {
+        let msg = invalid(GameConfig {
+            food_grow_snake: u32::MAX,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("food_grow_snake is 4294967295"), "{}", msg);
+        let msg = invalid(GameConfig {
+            mystery_score: MAX_MYSTERY_SCORE + 1,
+            ..GameConfig::default()
+        });
+        assert!(msg.starts_with("mystery_score is"), "{}", msg);
+        assert!(GameConfig {
+            fat_grow_snake: MAX_GROW_SNAKE,
+            mystery_grow_snake: MAX_GROW_SNAKE,
+            ..GameConfig::default()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn fit_width_stays_in_the_limits() {
+        let mut config = GameConfig {
+            field_height: 8,
+            ..GameConfig::default()
+        };
+        config.fit_width(1920, 1080);
+        assert_eq!(config.field_width, Some(1920 / (1080 / 10) - 2));
+        let mut config = GameConfig {
+            field_height: MAX_FIELD_SIZE,
+            ..GameConfig::default()
+        };
+        config.fit_width(100_000, 1002);
+        assert_eq!(config.field_width, Some(MAX_FIELD_SIZE));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let result = GameConfig::load(None, &["field_heigth=20".to_string()]);
+        match result {
+            Err(ConfigError::Parse(msg)) => assert!(msg.contains("field_heigth"), "{}", msg),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overrides_are_parsed_as_toml() {
+        let overrides = [
+            " field_height = 20 ".to_string(),
+            "wrap=true".to_string(),
+            "window=[800, 600]".to_string(),
+            "obstacle_p=0.5".to_string(),
+        ];
+        let config = GameConfig::load(None, &overrides).expect("Should be able to load");
+        assert_eq!(config.field_height, 20);
+        assert!(config.wrap);
+        assert_eq!(config.window, Some((800, 600)));
+        assert_eq!(config.obstacle_p, 0.5);
+        assert!(matches!(
+            GameConfig::load(None, &["wrap".to_string()]),
+            Err(ConfigError::Parse(msg)) if msg == "'wrap' is not key=value"
+        ));
+    }
+
+    #[test]
+    fn an_override_which_is_not_toml_is_a_string() {
+        // no value is a string, so the string is of the wrong type
+        match GameConfig::load(None, &["field_height=tall".to_string()]) {
+            Err(ConfigError::Parse(msg)) => {
+                assert!(msg.contains(r#"string "tall""#), "{}", msg)
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overrides_change_the_file() {
+        let path = std::env::temp_dir().join(format!("rnake-config-{}.toml", std::process::id()));
+        fs::write(&path, "field_height = 12\nwrap = true\n").expect("Should be able to write");
+        let config = GameConfig::load(Some(&path), &["field_height=16".to_string()]);
+        fs::remove_file(&path).expect("Should be able to remove the file");
+        let config = config.expect("Should be able to load");
+        assert_eq!(config.field_height, 16);
+        assert!(config.wrap);
+        assert_eq!(config.frame_delta, GameConfig::default().frame_delta);
+    }
+}
@@ -0,0 +1,191 @@
+//! Pictures of a replayed game without a window, e.g. for bug reports or on CI machines without
+//! a display: a PNG file per step, or an animated GIF. It does not need SDL2, build it with
+//! `cargo build --bin rnake-export --no-default-features --features export`.
+
+use std::cmp::min;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use resvg::tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
+
+use rnake::cli;
+use rnake::game::Game;
+use rnake::level;
+use rnake::pixmap::PixmapRenderer;
+use rnake::render::Renderer;
+use rnake::replay::Replay;
+
+const USAGE: &str = "Usage: rnake-export --replay <file> (--png <directory> | --gif <file>) \
+                     [--cell <pixels>] [--ticks <number>]";
+
+// the size of a cell of the field in pixels, unless given
+const CELL: u32 = 16;
+// the largest cell size; the images of all the sprites are made at this size
+const MAX_CELL: u32 = 256;
+// how hard the GIF encoder looks for the colors of a frame, from 1 (the best) to 30 (the fastest)
+const GIF_SPEED: i32 = 10;
+
+enum Output {
+    // a directory for the PNG files, one per step
+    Png(PathBuf),
+    // the animated GIF file
+    Gif(PathBuf),
+}
+
+/// Command line options
+struct Options {
+    replay: PathBuf,
+    output: Output,
+    cell: u32,
+    // stop after this many steps
+    ticks: Option<u64>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String> + 'static) -> Result<Self, String> {
+        let mut replay = None;
+        let mut output = None;
+        let mut cell = CELL;
+        let mut ticks = None;
+        cli::parse(args, |name, args| {
+            match name {
+                "--replay" => replay = Some(PathBuf::from(args.value()?)),
+                "--png" => output = Some(Output::Png(PathBuf::from(args.value()?))),
+                "--gif" => output = Some(Output::Gif(PathBuf::from(args.value()?))),
+                "--cell" => {
+                    cell = args.parsed("cell size")?;
+                    if !(1..=MAX_CELL).contains(&cell) {
+                        return Err(format!(
+                            "Bad cell size '{}', it must be between 1 and {}",
+                            cell, MAX_CELL
+                        ));
+                    }
+                }
+                "--ticks" => ticks = Some(args.parsed("number of ticks")?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(Options {
+            replay: replay.ok_or("The replay is needed")?,
+            output: output.ok_or("Either --png or --gif is needed")?,
+            cell,
+            ticks,
+        })
+    }
+}
+
+pub fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("{}\n{}", msg, USAGE);
+        process::exit(2);
+    });
+    let replay = Replay::load(&options.replay).unwrap_or_else(|e| {
+        eprintln!("{}: {}", options.replay.display(), e);
+        process::exit(2);
+    });
+    let exported = match &options.output {
+        Output::Png(dir) => export_png(&replay, &options, dir),
+        Output::Gif(path) => export_gif(&replay, &options, path),
+    };
+    if let Err(msg) = exported {
+        eprintln!("{}", msg);
+        process::exit(1);
+    }
+}
+
+/// Play the replay to the end of the game, drawing the game before the first step and after
+/// every step
+fn play(
+    replay: &Replay,
+    options: &Options,
+    mut frame: impl FnMut(&PixmapRenderer, u64) -> Result<(), String>,
+) -> Result<(), String> {
+    let end = match (options.ticks, replay.end) {
+        (Some(ticks), Some(end)) => min(ticks, end),
+        (Some(ticks), None) => ticks,
+        (None, Some(end)) => end,
+        (None, None) => {
+            return Err("The replay does not tell when the game was over, give --ticks".to_string())
+        }
+    };
+    let levels = level::campaign(&replay.config);
+    if replay.level >= levels.len() {
+        return Err(format!("There is no level {}", replay.level));
+    }
+    let mut game = Game::replay(&replay.config, &levels, replay);
+    let mut renderer = PixmapRenderer::new(options.cell);
+    renderer.draw(game.world());
+    frame(&renderer, game.world().tick())?;
+    while !game.over() && game.world().tick() < end {
+        game.step();
+        renderer.draw(game.world());
+        frame(&renderer, game.world().tick())?;
+    }
+    Ok(())
+}
+
+fn last_frame(renderer: &PixmapRenderer) -> &Pixmap {
+    renderer
+        .frame()
+        .expect("Programming error: the frame must be drawn before it is saved")
+}
+
+/// Save the frames to `<dir>/<tick>.png`
+fn export_png(replay: &Replay, options: &Options, dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    play(replay, options, |renderer, tick| {
+        let path = dir.join(format!("{:06}.png", tick));
+        last_frame(renderer)
+            .save_png(&path)
+            .map_err(|e| format!("Cannot save {}: {}", path.display(), e))
+    })
+}
+
+/// Save the frames as an animated GIF, as fast as the game was played. The levels may differ in
+/// size, so the frames are in the middle of a picture as large as the largest level.
+fn export_gif(replay: &Replay, options: &Options, path: &Path) -> Result<(), String> {
+    let levels = level::campaign(&replay.config);
+    let (width, height) = levels
+        .iter()
+        .skip(replay.level)
+        .fold((0, 0), |(width, height), level| {
+            (width.max(level.width), height.max(level.height))
+        });
+    // the wall around the field takes a cell on each side
+    let (width, height) = ((width + 2) * options.cell, (height + 2) * options.cell);
+    let too_large = || format!("The {}x{} picture is too large for a GIF", width, height);
+    let gif_width = u16::try_from(width).map_err(|_| too_large())?;
+    let gif_height = u16::try_from(height).map_err(|_| too_large())?;
+
+    let file =
+        File::create(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+    let cannot_write = |e: gif::EncodingError| format!("Cannot write {}: {}", path.display(), e);
+    let mut encoder = gif::Encoder::new(file, gif_width, gif_height, &[]).map_err(cannot_write)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(cannot_write)?;
+    // the delay is in hundredths of a second, and most viewers do not show shorter ones than 2
+    let delay = (replay.config.frame_delta / 10).clamp(2, u16::MAX as u64) as u16;
+
+    let mut picture = Pixmap::new(width, height).expect("Should be able to create pixmap");
+    play(replay, options, |renderer, _| {
+        let frame = last_frame(renderer);
+        picture.fill(Color::BLACK);
+        picture.draw_pixmap(
+            ((width - frame.width()) / 2) as i32,
+            ((height - frame.height()) / 2) as i32,
+            frame.as_ref(),
+            &PixmapPaint::default(),
+            Transform::identity(),
+            None,
+        );
+        // the picture is opaque, so its premultiplied colors are the plain ones
+        let mut rgba = picture.data().to_vec();
+        let mut gif_frame =
+            gif::Frame::from_rgba_speed(gif_width, gif_height, &mut rgba, GIF_SPEED);
+        gif_frame.delay = delay;
+        encoder.write_frame(&gif_frame).map_err(cannot_write)
+    })
+}
@@ -0,0 +1,380 @@
+//! A game from the first step to the end: the world, the turns of the players, the levels, the
+//! pauses and the replay. The frontends only read the input, keep the time and draw.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::config::GameConfig;
+use crate::input::{Steer, TurnQueue};
+use crate::level::Level;
+use crate::render::Renderer;
+use crate::replay::Replay;
+use crate::world::{StepError, StepOk, World};
+
+// after a pause, count down from this number, one step per COUNTDOWN_STEP
+const COUNTDOWN: u32 = 3;
+const COUNTDOWN_STEP: Duration = Duration::from_millis(500);
+// show short messages, like the name of the next level, for this long
+pub const MESSAGE_PAUSE: Duration = Duration::from_millis(1500);
+
+/// What the game shows besides the world, and the input it throws away while the player cannot
+/// play
+pub trait Screen: Renderer {
+    /// Show the lines over the game as it was drawn last
+    fn overlay(&mut self, lines: &[&str]);
+    /// Show the lines alone
+    fn messages(&mut self, lines: &[&str]);
+    /// Throw away the input which has come; true if some of it quits, which must not be missed
+    fn skip_input(&mut self) -> bool;
+}
+
+/// Whether the game is paused. It pauses on the pause key, when the player switches to another
+/// window, and while the frontend cannot show the game; it goes on only on the pause key.
+#[derive(Default)]
+pub struct Pause {
+    paused: bool,
+    // what has happened since the last frame
+    key: bool,
+    focus_lost: bool,
+    exposed: bool,
+}
+
+/// What the frontend does about the pause at a frame
+#[derive(Debug, PartialEq)]
+pub enum PauseChange {
+    Nothing,
+    // show the paused game: it has just paused, or it has to be drawn again
+    Show,
+    // the pause is over: go on after `Game::resume`
+    Resume,
+}
+
+impl Pause {
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The pause key is pressed
+    pub fn key(&mut self) {
+        self.key = true;
+    }
+
+    /// The player has switched to another window
+    pub fn focus_lost(&mut self) {
+        self.focus_lost = true;
+    }
+
+    /// The screen has to be drawn again, e.g. its size has changed
+    pub fn exposed(&mut self) {
+        self.exposed = true;
+    }
+
+    /// What has changed since the last frame; call it once per frame after reading the input.
+    /// `shown` is false while the frontend cannot show the game, e.g. its screen is too small.
+    pub fn update(&mut self, shown: bool) -> PauseChange {
+        let (key, focus_lost, exposed) = (self.key, self.focus_lost, self.exposed);
+        (self.key, self.focus_lost, self.exposed) = (false, false, false);
+        if self.paused && key && shown {
+            self.paused = false;
+            PauseChange::Resume
+        } else if key || focus_lost || (self.paused && exposed) || (!self.paused && !shown) {
+            self.paused = true;
+            PauseChange::Show
+        } else {
+            PauseChange::Nothing
+        }
+    }
+}
+
+/// Why the game is over
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Ending {
+    Quit,
+    // the replay played back is at the tick where the recorded game was over
+    ReplayOver,
+    // a snake died; the others are still alive, unless they died at the same time
+    Crash,
+}
+
+/// What a step of the game brought
+pub struct Step {
+    // what happened to every snake; empty if the replay is over
+    pub results: Vec<Result<StepOk, StepError>>,
+    // the next level has started
+    pub next_level: bool,
+}
+
+/// A game with a snake for every spawn on the level: one player on the levels of the campaign,
+/// or several players on an arena. The game is over when a snake dies.
+pub struct Game<'a> {
+    w: World,
+    levels: &'a [Level],
+    level: usize,
+    // one queue per snake
+    turns: Vec<TurnQueue>,
+    // the turns of a single player; None when playing back a replay
+    recording: Option<Replay>,
+    playback: Option<&'a Replay>,
+    ending: Option<Ending>,
+}
+
+impl<'a> Game<'a> {
+    /// A new game on the given level, going to the next ones as the player reaches the targets
+    pub fn new(config: &GameConfig, levels: &'a [Level], level: usize, seed: u64) -> Self {
+        let w = World::new(config.clone(), &levels[level], seed);
+        let recording = match w.snakes.len() {
+            1 => Some(Replay::new(seed, config.clone(), level)),
+            _ => None,
+        };
+        Game {
+            turns: (0..w.snakes.len())
+                .map(|_| TurnQueue::new(config.turn_queue))
+                .collect(),
+            w,
+            levels,
+            level,
+            recording,
+            playback: None,
+            ending: None,
+        }
+    }
+
+    /// The recorded game played back; the turns come from the replay and not from the players
+    pub fn replay(config: &GameConfig, levels: &'a [Level], replay: &'a Replay) -> Self {
+        let mut game = Game::new(config, levels, replay.level, replay.seed);
+        game.recording = None;
+        game.playback = Some(replay);
+        game
+    }
+
+    pub fn world(&self) -> &World {
+        &self.w
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.levels[self.level]
+    }
+
+    /// The index of the current level in the levels of the game
+    pub fn level_index(&self) -> usize {
+        self.level
+    }
+
+    pub fn players(&self) -> usize {
+        self.turns.len()
+    }
+
+    /// Remember the player's turn for the next steps; a replay played back ignores it
+    pub fn steer(&mut self, player: usize, steer: Steer) {
+        if self.playback.is_none() {
+            self.turns[player].push(steer);
+        }
+    }
+
+    /// Make one turn per snake and move them; call it only while the game is not over.
+    ///
+    /// The turns are recorded when they are made, not when the keys are pressed.
+    pub fn step(&mut self) -> Step {
+        assert!(
+            self.ending.is_none(),
+            "Programming error: the game must not go on after it is over"
+        );
+        match self.playback {
+            Some(replay) => {
+                if replay.end == Some(self.w.tick()) {
+                    self.end(Ending::ReplayOver);
+                    return Step {
+                        results: vec![],
+                        next_level: false,
+                    };
+                }
+                if let Some(turn) = replay.turn_at(self.w.tick()) {
+                    self.w.turn(turn);
+                }
+            }
+            None => {
+                let tick = self.w.tick();
+                for (queue, snake) in self.turns.iter_mut().zip(&mut self.w.snakes) {
+                    let turn = queue.apply(snake);
+                    if let (Some(turn), Some(recording)) = (turn, &mut self.recording) {
+                        recording.record(tick, turn);
+                    }
+                }
+            }
+        }
+
+        let results = self.w.step_all();
+        if results.iter().any(|r| r.is_err()) {
+            self.end(Ending::Crash);
+            return Step {
+                results,
+                next_level: false,
+            };
+        }
+        let next_level = self.w.level_complete() && self.level + 1 < self.levels.len();
+        if next_level {
+            self.level += 1;
+            self.w.load_level(&self.levels[self.level]);
+            // the turns made on the old level are not for the new one
+            for queue in &mut self.turns {
+                queue.clear();
+            }
+        }
+        Step {
+            results,
+            next_level,
+        }
+    }
+
+    /// Count down over the paused game before it goes on. The input which comes meanwhile is not
+    /// for the game, but a quit in it ends the game; returns false then.
+    pub fn resume(&mut self, screen: &mut impl Screen) -> bool {
+        countdown(|n| {
+            screen.draw(&self.w);
+            screen.overlay(&[n]);
+        });
+        self.skip_input(screen)
+    }
+
+    /// Show the title of the level which has just started for a while. The turns made meanwhile
+    /// are for the old level, but a quit ends the game; returns false then.
+    pub fn show_level(&mut self, screen: &mut impl Screen) -> bool {
+        let lines = self.level_lines();
+        let lines: Vec<&str> = lines.iter().map(|line| line.as_ref()).collect();
+        screen.messages(&lines);
+        thread::sleep(MESSAGE_PAUSE);
+        self.skip_input(screen)
+    }
+
+    fn skip_input(&mut self, screen: &mut impl Screen) -> bool {
+        if screen.skip_input() {
+            self.quit();
+            return false;
+        }
+        true
+    }
+
+    /// End the game before a snake dies
+    pub fn quit(&mut self) {
+        if self.ending.is_none() {
+            self.end(Ending::Quit);
+        }
+    }
+
+    pub fn over(&self) -> bool {
+        self.ending.is_some()
+    }
+
+    fn end(&mut self, ending: Ending) {
+        self.ending = Some(ending);
+        if let Some(recording) = &mut self.recording {
+            recording.finish(self.w.tick());
+        }
+    }
+
+    /// The replay of a single player's game, finished when the game is over
+    pub fn recording(&self) -> Option<&Replay> {
+        self.recording.as_ref()
+    }
+
+    /// The title of the current level, its name and its password, which lets the player come
+    /// back to it later
+    pub fn level_lines(&self) -> Vec<String> {
+        let level = self.level();
+        let mut lines = vec![format!("Level {}", self.level + 1), level.name.clone()];
+        lines.extend(level.password.as_ref().map(|p| format!("Password: {}", p)));
+        lines
+    }
+
+    /// How the game ended and the scores, to be shown when it is over
+    pub fn summary(&self) -> Vec<String> {
+        if self.players() > 1 {
+            return self.versus_summary();
+        }
+        let reason = match (self.ending, self.w.snake().dead) {
+            (Some(Ending::ReplayOver), _) => "The replay is over.".to_string(),
+            (Some(Ending::Crash), Some(e)) => format!("The snake {}.", e),
+            _ => "You have exited the game.".to_string(),
+        };
+        vec![
+            reason,
+            "Game over.".to_string(),
+            format!("Level {}: {}.", self.level + 1, self.level().name),
+            format!("Score {}.", self.w.score()),
+            format!("Seed {}.", self.w.seed()),
+        ]
+    }
+
+    /// Who has won and how every snake did
+    fn versus_summary(&self) -> Vec<String> {
+        let mut lines = vec![match self.ending {
+            Some(Ending::Crash) => self.winner(),
+            _ => "You have exited the game.".to_string(),
+        }];
+        for (player, snake) in self.w.snakes.iter().enumerate() {
+            let how = snake.dead.map_or(String::new(), |e| format!(", {}", e));
+            lines.push(format!(
+                "Player {}: score {}{}.",
+                player + 1,
+                snake.score,
+                how
+            ));
+        }
+        lines
+    }
+
+    /// The last snake in the game, or the one with the highest score if the last ones died at
+    /// the same time
+    fn winner(&self) -> String {
+        let snakes = &self.w.snakes;
+        let alive: Vec<usize> = (0..snakes.len())
+            .filter(|idx| snakes[*idx].dead.is_none())
+            .collect();
+        if let [player] = alive[..] {
+            return format!("Player {} wins!", player + 1);
+        }
+        let best = snakes.iter().map(|s| s.score).max().unwrap_or_default();
+        let best: Vec<usize> = (0..snakes.len())
+            .filter(|idx| snakes[*idx].score == best)
+            .collect();
+        match best[..] {
+            [player] => format!("Player {} wins on score!", player + 1),
+            _ => "It is a draw.".to_string(),
+        }
+    }
+}
+
+/// Count down before the paused game goes on, drawing every number over the game
+fn countdown(mut draw: impl FnMut(&str)) {
+    for n in (1..=COUNTDOWN).rev() {
+        draw(&n.to_string());
+        thread::sleep(COUNTDOWN_STEP);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_game_goes_on_only_on_the_pause_key() {
+        let mut pause = Pause::default();
+        assert_eq!(pause.update(true), PauseChange::Nothing);
+        pause.focus_lost();
+        assert_eq!(pause.update(true), PauseChange::Show);
+        // coming back to the window only draws the paused game again
+        pause.exposed();
+        assert_eq!(pause.update(true), PauseChange::Show);
+        assert!(pause.paused());
+        pause.key();
+        assert_eq!(pause.update(true), PauseChange::Resume);
+        assert!(!pause.paused());
+        // the screen which cannot show the game pauses it, and the key does not help then
+        assert_eq!(pause.update(false), PauseChange::Show);
+        pause.key();
+        assert_eq!(pause.update(false), PauseChange::Show);
+        assert_eq!(pause.update(true), PauseChange::Nothing);
+        pause.key();
+        assert_eq!(pause.update(true), PauseChange::Resume);
+    }
+}
@@ -0,0 +1,143 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+// how many best games the table keeps
+pub const MAX_ENTRIES: usize = 8;
+
+#[derive(Debug)]
+pub enum HighScoreError {
+    Io(PathBuf, io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for HighScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HighScoreError::Io(path, e) => write!(f, "cannot access {}: {}", path.display(), e),
+            HighScoreError::Parse(msg) => write!(f, "bad high score table: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for HighScoreError {}
+
+/// One game in the table
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+    // YYYY-MM-DD, in UTC
+    pub date: String,
+    // how the game was played, e.g. its speed; the frontend decides
+    pub mode: String,
+    pub seed: u64,
+}
+
+/// The best games, the best first. It is saved as TOML, one `[[entries]]` table per game.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighScores {
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+}
+
+impl HighScores {
+    /// Read the table from the file; there is no table (and no error) if there is no file yet.
+    pub fn load(path: &Path) -> Result<Self, HighScoreError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(HighScoreError::Io(path.to_owned(), e)),
+        };
+        let mut scores: HighScores = toml::from_str(&text)
+            .map_err(|e| HighScoreError::Parse(e.to_string().trim_end().replace('\n', " ")))?;
+        // a table edited by hand still shows the best games first
+        scores.entries.sort_by_key(|e| Reverse(e.score));
+        scores.entries.truncate(MAX_ENTRIES);
+        Ok(scores)
+    }
+
+    /// Write the table to the file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<(), HighScoreError> {
+        let text = toml::to_string(self).expect("Should be able to serialize high scores");
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| HighScoreError::Io(dir.to_owned(), e))?;
+        }
+        fs::write(path, text).map_err(|e| HighScoreError::Io(path.to_owned(), e))
+    }
+
+    /// Whether the game with this score gets into the table
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Put the game into the table, below the games with the same score. Returns its place
+    /// (counting from 0), or None if the score is not good enough.
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        let place = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(place, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        Some(place)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> Entry {
+        Entry {
+            name: name.to_string(),
+            score,
+            date: "2024-01-01".to_string(),
+            mode: "normal".to_string(),
+            seed: 0,
+        }
+    }
+
+    fn names(scores: &HighScores) -> Vec<&str> {
+        scores.entries.iter().map(|e| e.name.as_ref()).collect()
+    }
+
+    #[test]
+    fn ties_go_below() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.insert(entry("first", 10)), Some(0));
+        assert_eq!(scores.insert(entry("second", 10)), Some(1));
+        assert_eq!(scores.insert(entry("best", 20)), Some(0));
+        assert_eq!(scores.insert(entry("third", 10)), Some(3));
+        assert_eq!(names(&scores), ["best", "first", "second", "third"]);
+        // nothing is not a score
+        assert_eq!(scores.insert(entry("none", 0)), None);
+    }
+
+    #[test]
+    fn full_table_cutoff() {
+        let mut scores = HighScores::default();
+        for score in 1..=MAX_ENTRIES as u32 {
+            scores.insert(entry(&score.to_string(), score * 10));
+        }
+        assert_eq!(scores.entries.len(), MAX_ENTRIES);
+        // a tie with the last game does not push it out
+        assert!(!scores.qualifies(10));
+        assert_eq!(scores.insert(entry("tie", 10)), None);
+        assert!(scores.qualifies(11));
+        assert_eq!(scores.insert(entry("better", 11)), Some(MAX_ENTRIES - 1));
+        assert_eq!(scores.entries.len(), MAX_ENTRIES);
+        assert_eq!(names(&scores).last(), Some(&"better"));
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+use crate::world::{Direction, Snake, Turn};
+
+/// What the player asked the snake to do
+#[derive(Clone, Debug, PartialEq)]
+pub enum Steer {
+    // turn relative to where the head looks
+    Turn(Turn),
+    // look in this direction on the screen
+    Direction(Direction),
+}
+
+/// The turns the player has made but the snake has not yet.
+///
+/// The snake turns at most once per step, so the turns made faster than that wait here for the
+/// next steps instead of being lost. When the queue is full, new turns are dropped.
+pub struct TurnQueue {
+    depth: usize,
+    pending: VecDeque<Steer>,
+}
+
+impl TurnQueue {
+    pub fn new(depth: usize) -> Self {
+        TurnQueue {
+            depth,
+            pending: VecDeque::with_capacity(depth),
+        }
+    }
+
+    /// Add the turn to the queue; false if the queue is full
+    pub fn push(&mut self, steer: Steer) -> bool {
+        if self.pending.len() >= self.depth {
+            return false;
+        }
+        self.pending.push_back(steer);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Make the first waiting turn which turns the player's snake; call it once before every step.
+    ///
+    /// Directions which do not turn the snake when their time comes (the head already looks
+    /// there, or it is back into the neck) are skipped. Returns the turn made, to be recorded.
+    pub fn apply(&mut self, snake: &mut Snake) -> Option<Turn> {
+        while let Some(steer) = self.pending.pop_front() {
+            match steer {
+                Steer::Turn(turn) => {
+                    snake.turn(&turn);
+                    return Some(turn);
+                }
+                Steer::Direction(dir) => {
+                    if let Some(turn) = snake.set_direction(dir) {
+                        return Some(turn);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::world::{test_world, World};
+
+    fn step(queue: &mut TurnQueue, world: &mut World) -> Option<Turn> {
+        let turn = queue.apply(world.snake_mut());
+        assert!(world.step().is_ok(), "the snake should survive the step");
+        turn
+    }
+
+    #[test]
+    fn one_turn_per_step() {
+        let mut w = test_world(&Level::open(20, 20, None), 1);
+        let mut queue = TurnQueue::new(3);
+        assert!(queue.push(Steer::Turn(Turn::Left)));
+        assert!(queue.push(Steer::Turn(Turn::Left)));
+
+        assert_eq!(step(&mut queue, &mut w), Some(Turn::Left));
+        assert_eq!(w.snake().head().dir, Direction::Left);
+        assert_eq!(queue.len(), 1);
+
+        assert_eq!(step(&mut queue, &mut w), Some(Turn::Left));
+        assert_eq!(w.snake().head().dir, Direction::Down);
+        assert!(queue.is_empty());
+
+        assert_eq!(step(&mut queue, &mut w), None);
+        assert_eq!(w.snake().head().dir, Direction::Down);
+    }
+
+    #[test]
+    fn u_turn_into_the_next_lane() {
+        let mut w = test_world(&Level::open(20, 20, None), 1);
+        let (x, y) = (w.snake().head().coords.x, w.snake().head().coords.y);
+        let mut queue = TurnQueue::new(3);
+        queue.push(Steer::Turn(Turn::Right));
+        queue.push(Steer::Turn(Turn::Right));
+        step(&mut queue, &mut w);
+        step(&mut queue, &mut w);
+        assert_eq!(w.snake().head().dir, Direction::Down);
+        // the head is next to where the neck was, going the other way
+        assert_eq!(
+            (w.snake().head().coords.x, w.snake().head().coords.y),
+            (x + 1, y + 1)
+        );
+    }
+
+    #[test]
+    fn full_queue_drops_turns() {
+        let mut queue = TurnQueue::new(2);
+        assert!(queue.push(Steer::Turn(Turn::Left)));
+        assert!(queue.push(Steer::Turn(Turn::Right)));
+        assert!(!queue.push(Steer::Turn(Turn::Left)));
+        assert_eq!(queue.len(), 2);
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn directions_are_checked_when_applied() {
+        let mut w = test_world(&Level::open(20, 20, None), 1);
+        let mut queue = TurnQueue::new(3);
+        // up is where the snake already goes, down is into the neck: both are skipped
+        queue.push(Steer::Direction(Direction::Up));
+        queue.push(Steer::Direction(Direction::Down));
+        queue.push(Steer::Direction(Direction::Right));
+        assert_eq!(step(&mut queue, &mut w), Some(Turn::Right));
+        assert!(queue.is_empty());
+
+        // going right, up and then left is a U-turn made in two steps
+        queue.push(Steer::Direction(Direction::Up));
+        queue.push(Steer::Direction(Direction::Left));
+        assert_eq!(step(&mut queue, &mut w), Some(Turn::Left));
+        assert_eq!(w.snake().head().dir, Direction::Up);
+        assert_eq!(step(&mut queue, &mut w), Some(Turn::Left));
+        assert_eq!(w.snake().head().dir, Direction::Left);
+    }
+
+    #[test]
+    fn applied_turns_replay_the_same_game() {
+        let mut w = test_world(&Level::open(20, 20, None), 1);
+        let mut queue = TurnQueue::new(3);
+        let presses = [
+            (0, Steer::Turn(Turn::Left)),
+            (0, Steer::Turn(Turn::Left)),
+            (4, Steer::Direction(Direction::Right)),
+        ];
+        let mut turns = vec![];
+        for tick in 0..8 {
+            for (_, steer) in presses.iter().filter(|(t, _)| *t == tick) {
+                queue.push(steer.clone());
+            }
+            // recorded as the replay does, with the tick before the step
+            if let Some(turn) = step(&mut queue, &mut w) {
+                turns.push((tick, turn));
+            }
+        }
+        assert_eq!(turns.len(), 3);
+
+        let mut again = test_world(&Level::open(20, 20, None), 1);
+        for tick in 0..w.tick() {
+            for (_, turn) in turns.iter().filter(|(t, _)| *t == tick) {
+                again.turn(turn);
+            }
+            assert!(again.step().is_ok());
+        }
+        assert_eq!(again.snake().head().coords.x, w.snake().head().coords.x);
+        assert_eq!(again.snake().head().coords.y, w.snake().head().coords.y);
+        assert_eq!(again.snake().head().dir, w.snake().head().dir);
+    }
+}
@@ -0,0 +1,393 @@
+use std::fmt;
+
+use crate::config::{GameConfig, MIN_FIELD_SIZE};
+use crate::world::Direction;
+
+// how many points to score on the open field before going to the designed levels
+const OPEN_FIELD_TARGET: u32 = 10;
+
+#[derive(Debug)]
+pub enum LevelError {
+    // the line number (counting from 1) and what is wrong with it
+    Format(usize, String),
+    Invalid(String),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Format(line, msg) => write!(f, "bad level, line {}: {}", line, msg),
+            LevelError::Invalid(msg) => write!(f, "invalid level: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+/// The layout of the field: its size, the permanent walls on it and where the snakes start.
+///
+/// A level file starts with `key: value` lines (`name`, and optionally `target`, the score to
+/// make on the level to go to the next one, and `password`, which lets the player start the
+/// game on this level), then after an empty line comes the map of the
+/// field, one text line per row of cells: `.` is an empty cell, `#` is a wall, and one of
+/// `^`, `v`, `<`, `>` is the head of a snake looking in that direction. The rest of the snake
+/// is behind the head. There is a snake for every player, numbered as the map is read: row by
+/// row from the top, and from left to right within a row.
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    // coordinates are from 1 to width and height
+    pub walls: Vec<(u32, u32)>,
+    pub spawns: Vec<Spawn>,
+    // go to the next level after scoring this much on this one; never if None
+    pub target: Option<u32>,
+    pub password: Option<String>,
+}
+
+impl Level {
+    /// An empty field of the given size with the snake in the middle, looking up
+    pub fn open(width: u32, height: u32, target: Option<u32>) -> Self {
+        Level {
+            name: "Open field".to_string(),
+            width,
+            height,
+            walls: vec![],
+            spawns: vec![Spawn::new(width / 2, height / 2, Direction::Up)],
+            target,
+            password: None,
+        }
+    }
+
+    /// An empty field of the given size for two players, who start in the opposite corners of
+    /// the middle, the first one looking up and the second one looking down
+    pub fn arena(width: u32, height: u32) -> Self {
+        Level {
+            name: "Arena".to_string(),
+            width,
+            height,
+            walls: vec![],
+            spawns: vec![
+                Spawn::new(width / 3, height / 2, Direction::Up),
+                Spawn::new(
+                    width + 1 - width / 3,
+                    height + 1 - height / 2,
+                    Direction::Down,
+                ),
+            ],
+            target: None,
+            password: None,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        let mut lines = text.lines().enumerate().map(|(n, l)| (n + 1, l.trim_end()));
+        let mut name = None;
+        let mut target = None;
+        let mut password = None;
+        for (n, line) in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| LevelError::Format(n, "expected 'key: value'".to_string()))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "target" => {
+                    let bad = || LevelError::Format(n, format!("bad target '{}'", value));
+                    target = Some(value.parse().map_err(|_| bad())?);
+                }
+                "password" => password = Some(value.to_string()),
+                key => return Err(LevelError::Format(n, format!("unknown key '{}'", key))),
+            }
+        }
+        let name = name.ok_or_else(|| LevelError::Invalid("no name".to_string()))?;
+
+        let mut walls = vec![];
+        let mut spawns = vec![];
+        let mut width = None;
+        let mut height = 0;
+        for (n, line) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            height += 1;
+            let row_width = line.chars().count() as u32;
+            if *width.get_or_insert(row_width) != row_width {
+                return Err(LevelError::Format(
+                    n,
+                    "all rows of the map must be of the same width".to_string(),
+                ));
+            }
+            for (x, c) in (1..).zip(line.chars()) {
+                let direction = match c {
+                    '.' => continue,
+                    '#' => {
+                        walls.push((x, height));
+                        continue;
+                    }
+                    '^' => Direction::Up,
+                    'v' => Direction::Down,
+                    '<' => Direction::Left,
+                    '>' => Direction::Right,
+                    c => return Err(LevelError::Format(n, format!("unknown cell '{}'", c))),
+                };
+                spawns.push(Spawn::new(x, height, direction));
+            }
+        }
+        if spawns.is_empty() {
+            return Err(LevelError::Invalid("no snake on the map".to_string()));
+        }
+        let level = Level {
+            name,
+            width: width.unwrap_or(0),
+            height,
+            walls,
+            spawns,
+            target,
+            password,
+        };
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn validate(&self) -> Result<(), LevelError> {
+        if self.width < MIN_FIELD_SIZE || self.height < MIN_FIELD_SIZE {
+            return Err(LevelError::Invalid(format!(
+                "the map is {}x{}, but it must be at least {}x{}",
+                self.width, self.height, MIN_FIELD_SIZE, MIN_FIELD_SIZE
+            )));
+        }
+        let mut taken = vec![];
+        for (x, y) in self.spawns.iter().flat_map(|spawn| spawn.cells()) {
+            if x < 1 || y < 1 || x > self.width as i64 || y > self.height as i64 {
+                return Err(LevelError::Invalid(
+                    "the snake does not fit on the map".to_string(),
+                ));
+            }
+            if self.walls.contains(&(x as u32, y as u32)) {
+                return Err(LevelError::Invalid("the snake is in a wall".to_string()));
+            }
+            if taken.contains(&(x, y)) {
+                return Err(LevelError::Invalid("the snakes overlap".to_string()));
+            }
+            taken.push((x, y));
+        }
+        Ok(())
+    }
+}
+
+/// Where a snake starts: its head, and the direction the head looks in
+#[derive(Clone, Debug)]
+pub struct Spawn {
+    pub x: u32,
+    pub y: u32,
+    pub direction: Direction,
+}
+
+impl Spawn {
+    pub fn new(x: u32, y: u32, direction: Direction) -> Self {
+        Spawn { x, y, direction }
+    }
+
+    /// The cells of the initial snake, from the head to the tail
+    pub fn cells(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (dx, dy) = match self.direction {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (1, 0),
+            Direction::Right => (-1, 0),
+        };
+        let (x, y) = (self.x as i64, self.y as i64);
+        (0..3).map(move |i| (x + i * dx, y + i * dy))
+    }
+}
+
+/// All the levels in the order they are played: the open field of the configured size first,
+/// then the designed ones. The last level never ends.
+pub fn campaign(config: &GameConfig) -> Vec<Level> {
+    let mut levels = vec![Level::open(
+        config.width(),
+        config.field_height,
+        Some(OPEN_FIELD_TARGET),
+    )];
+    for text in [
+        include_str!("levels/01.txt"),
+        include_str!("levels/02.txt"),
+        include_str!("levels/03.txt"),
+    ] {
+        levels.push(Level::parse(text).expect("Programming error: bad built-in level"));
+    }
+    levels
+}
+
+/// The index of the level with the given password; passwords are not case-sensitive
+pub fn find_by_password(levels: &[Level], password: &str) -> Option<usize> {
+    let password = password.trim();
+    levels.iter().position(|level| {
+        level
+            .password
+            .as_ref()
+            .is_some_and(|p| p.eq_ignore_ascii_case(password))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A level named Test with the given map
+    fn parse_map(rows: &[&str]) -> Result<Level, LevelError> {
+        Level::parse(&format!("name: Test\n\n{}\n", rows.join("\n")))
+    }
+
+    /// An 8x8 map with a wall in the top left corner and the given row at the bottom
+    fn map_with_last_row(last: &str) -> Vec<String> {
+        let mut rows = vec!["#.......".to_string()];
+        rows.extend((0..6).map(|_| "........".to_string()));
+        rows.push(last.to_string());
+        rows
+    }
+
+    fn parse_rows(rows: &[String]) -> Result<Level, LevelError> {
+        parse_map(&rows.iter().map(|r| r.as_ref()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_a_map() {
+        let mut rows = map_with_last_row("........");
+        rows[3] = "...^....".to_string();
+        let level = Level::parse(&format!(
+            "name: Test\ntarget: 7\npassword: SECRET\n\n{}\n",
+            rows.join("\n")
+        ))
+        .expect("Should be able to parse the level");
+        assert_eq!((level.width, level.height), (8, 8));
+        assert_eq!(level.walls, vec![(1, 1)]);
+        assert_eq!(level.spawns.len(), 1);
+        assert_eq!((level.spawns[0].x, level.spawns[0].y), (4, 4));
+        assert_eq!(level.spawns[0].direction, Direction::Up);
+        assert_eq!(level.target, Some(7));
+        assert_eq!(level.password.as_deref(), Some("SECRET"));
+    }
+
+    #[test]
+    fn spawns_are_numbered_row_by_row() {
+        let mut rows = map_with_last_row("........");
+        rows[2] = "......v.".to_string();
+        rows[4] = ".^......".to_string();
+        let level = parse_rows(&rows).expect("Should be able to parse the level");
+        let heads: Vec<(u32, u32)> = level.spawns.iter().map(|s| (s.x, s.y)).collect();
+        assert_eq!(heads, vec![(7, 3), (2, 5)]);
+    }
+
+    #[test]
+    fn bad_key() {
+        assert!(matches!(
+            Level::parse("name: Test\nspeed: 3\n\n"),
+            Err(LevelError::Format(2, msg)) if msg == "unknown key 'speed'"
+        ));
+        assert!(matches!(
+            Level::parse("name: Test\ntarget: many\n\n"),
+            Err(LevelError::Format(2, _))
+        ));
+        assert!(matches!(
+            Level::parse("name Test\n\n"),
+            Err(LevelError::Format(1, _))
+        ));
+    }
+
+    #[test]
+    fn uneven_rows() {
+        let mut rows = map_with_last_row("...^....");
+        rows[5] = ".........".to_string();
+        // the map starts on line 3
+        assert!(matches!(
+            parse_rows(&rows),
+            Err(LevelError::Format(8, msg)) if msg.contains("same width")
+        ));
+    }
+
+    #[test]
+    fn unknown_cell() {
+        let mut rows = map_with_last_row("...^....");
+        rows[1] = "...@....".to_string();
+        assert!(matches!(
+            parse_rows(&rows),
+            Err(LevelError::Format(4, msg)) if msg == "unknown cell '@'"
+        ));
+    }
+
+    #[test]
+    fn snake_must_fit() {
+        // the snake looking down has its body above the head, out of the map
+        let mut rows = map_with_last_row("........");
+        rows[0] = "#..v....".to_string();
+        assert!(matches!(
+            parse_rows(&rows),
+            Err(LevelError::Invalid(msg)) if msg.contains("does not fit")
+        ));
+        let mut rows = map_with_last_row("........");
+        rows[1] = "<.......".to_string();
+        assert!(parse_rows(&rows).is_ok());
+        rows[1] = ".......<".to_string();
+        assert!(matches!(
+            parse_rows(&rows),
+            Err(LevelError::Invalid(msg)) if msg.contains("does not fit")
+        ));
+    }
+
+    #[test]
+    fn snake_in_a_wall() {
+        let mut rows = map_with_last_row("........");
+        rows[1] = "v.......".to_string();
+        assert!(matches!(
+            parse_rows(&rows),
+            Err(LevelError::Invalid(msg)) if msg == "the snake is in a wall"
+        ));
+    }
+
+    #[test]
+    fn no_snake_or_too_small() {
+        assert!(matches!(
+            parse_rows(&map_with_last_row("........")),
+            Err(LevelError::Invalid(msg)) if msg == "no snake on the map"
+        ));
+        assert!(matches!(
+            parse_map(&["...^...", ".......", ".......", "......."]),
+            Err(LevelError::Invalid(msg)) if msg.contains("at least")
+        ));
+    }
+
+    #[test]
+    fn built_in_levels() {
+        let levels = campaign(&GameConfig::default());
+        assert_eq!(levels.len(), 4);
+        for (idx, level) in levels.iter().enumerate().skip(1) {
+            assert_eq!((level.width, level.height), (40, 22), "{}", level.name);
+            assert_eq!(level.spawns.len(), 1, "{}", level.name);
+            assert!(level.password.is_some(), "{}", level.name);
+            // the last level never ends
+            assert_eq!(
+                level.target.is_some(),
+                idx + 1 < levels.len(),
+                "{}",
+                level.name
+            );
+        }
+    }
+
+    #[test]
+    fn passwords_are_trimmed_and_not_case_sensitive() {
+        let levels = campaign(&GameConfig::default());
+        assert_eq!(find_by_password(&levels, "STONES"), Some(1));
+        assert_eq!(find_by_password(&levels, "  hallway\n"), Some(2));
+        assert_eq!(find_by_password(&levels, "CrAtE"), Some(3));
+        assert_eq!(find_by_password(&levels, "CRATES"), None);
+        // the open field has no password
+        assert_eq!(find_by_password(&levels, ""), None);
+    }
+}
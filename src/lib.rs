@@ -0,0 +1,19 @@
+//! Game logic of Rnake without any frontend.
+//!
+//! The library does not depend on SDL2, so bots, tests and alternative frontends can drive
+//! [`world::World`] directly. Build it with `cargo build --lib --no-default-features`.
+
+pub mod autopilot;
+pub mod batch;
+pub mod cli;
+pub mod config;
+pub mod game;
+pub mod highscore;
+pub mod input;
+pub mod level;
+#[cfg(feature = "image")]
+pub mod pixmap;
+pub mod render;
+pub mod replay;
+pub mod time;
+pub mod world;
@@ -1,268 +1,426 @@
+mod bindings;
+mod menu;
+mod options;
+mod sdlrender;
 mod sdlwrapper;
 mod sound;
-mod world;
+mod versus;
 
 use std::cmp::min;
+use std::path::PathBuf;
+use std::process;
 
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::sys::{SDL_Delay, SDL_GetTicks64, Uint32, Uint64};
 
-use sdlwrapper::SDLWrapper;
-use world::{Direction, StepError, StepOk, Thing, World, FIELD_SIZE};
+use bindings::{Action, Bindings};
+use menu::Start;
+use options::Options;
+use rnake::config::GameConfig;
+use rnake::game::{self, Game, Pause, PauseChange};
+use rnake::highscore::{Entry, HighScores};
+use rnake::input::Steer;
+use rnake::level;
+use rnake::render::Renderer;
+use rnake::replay::Replay;
+use rnake::time;
+use rnake::world::{StepError, StepOk, Turn, World};
+use sdlwrapper::{SDLWrapper, Typed};
+
+const USAGE: &str = "Usage: rnake [--config <file>] [--set <key>=<value>]... \
+                     [--seed <number>] [--replay <file>] [--window <width>x<height>]";
 
-const FRAME_DELTA: Uint64 = 60;
 // update screen after the given number of SDL ticks
 const WAIT: Uint64 = 20;
+// show short messages, like the name of the next level, for this many milliseconds
+pub const MESSAGE_PAUSE: Uint32 = game::MESSAGE_PAUSE.as_millis() as Uint32;
 
 pub fn main() {
-    let ttf_context = sdl2::ttf::init().expect("Should be able to construct TTF context");
-    let mut sdl = SDLWrapper::new(&FIELD_SIZE, &ttf_context);
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("{}\n{}", msg, USAGE);
+        process::exit(2);
+    });
+    // when playing back a replay, the turns come from it and not from the keyboard
+    let playback = options.replay.as_deref().map(|path| {
+        Replay::load(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path.display(), e);
+            process::exit(2);
+        })
+    });
+    // a replay brings its own configuration
+    let mut config = match &playback {
+        Some(replay) => replay.config.clone(),
+        None => options.game.load().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        }),
+    };
 
-    sdl.sounds.start();
-    sdl.messages(vec!["Press SPACE to start the game"]);
-    'waiting_start: loop {
-        for event in sdl.events.poll_iter() {
-            match event {
-                Event::KeyDown {
-                    keycode: Some(Keycode::Space),
-                    ..
-                } => {
-                    break 'waiting_start;
-                }
-                _ => unsafe {
-                    SDL_Delay(100);
-                },
-            }
-        }
+    // the window size from the command line is for the replay too
+    if options.window.is_some() {
+        config.window = options.window;
+        config.validate().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        });
     }
 
-    let mut quit_msg = "You have exited the game.";
+    let ttf_context = sdl2::ttf::init().expect("Should be able to construct TTF context");
+    let mut sdl = SDLWrapper::new(&ttf_context, config.window);
+    let (area_width, area_height) = sdl.field_area();
+    config.fit_width(area_width, area_height);
+    let levels = level::campaign(&config);
+    let mut first_level = playback.as_ref().map_or(0, |replay| replay.level);
+    if first_level >= levels.len() {
+        eprintln!("There is no level {}", first_level);
+        process::exit(2);
+    }
 
-    'game: loop {
-        let mut w = World::init();
+    // the levels up to the furthest one reached by a password can be chosen without it again
+    let mut unlocked = first_level;
 
-        let mut next_frame: Uint64 = 0;
-        let mut turned = false;
+    let mut bindings = Bindings::load();
+    let mut scores = high_scores_path().map_or_else(HighScores::default, |path| {
+        HighScores::load(&path).unwrap_or_else(|e| {
+            eprintln!("Ignoring the high scores: {}", e);
+            HighScores::default()
+        })
+    });
 
-        'running: loop {
-            // process quit and turn the snake events
-            for event in sdl.events.poll_iter() {
-                match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => {
-                        break 'running;
+    sdl.sounds.start();
+    'title: loop {
+        if playback.is_some() {
+            let start = format!("Press {} to watch the replay", bindings.confirm.name());
+            sdl.messages(vec![start.as_ref()]);
+            'waiting_start: loop {
+                for event in sdl.poll_events() {
+                    if matches!(event, Event::Quit { .. }) {
+                        break 'title;
                     }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Right),
-                        ..
-                    } => {
-                        if !turned {
-                            w.turn_right();
-                            turned = true;
-                        }
+                    if bindings.confirms(&event) {
+                        break 'waiting_start;
                     }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Left),
-                        ..
-                    } => {
-                        if !turned {
-                            w.turn_left();
-                            turned = true;
-                        }
+                }
+                unsafe {
+                    SDL_Delay(100);
+                }
+            }
+        } else {
+            match menu::title(
+                &mut sdl,
+                &mut config,
+                &mut bindings,
+                &levels,
+                &mut first_level,
+                &mut unlocked,
+                &scores,
+            ) {
+                Start::Game => {}
+                Start::Versus => {
+                    if versus::play(&mut sdl, &config, &bindings) {
+                        break 'title;
                     }
-                    _ => {}
+                    continue 'title;
                 }
+                Start::Quit => break 'title,
             }
+        }
+
+        'game: loop {
+            let mut game = match &playback {
+                Some(replay) => Game::replay(&config, &levels, replay),
+                None => Game::new(
+                    &config,
+                    &levels,
+                    first_level,
+                    options.seed.unwrap_or_else(rand::random),
+                ),
+            };
+            let closed = run(&mut sdl, &bindings, &mut game);
 
-            // check if we are at the right moment
-            unsafe {
-                if SDL_GetTicks64() < next_frame {
-                    SDL_Delay(min(
-                        WAIT as Uint32,
-                        (next_frame - SDL_GetTicks64()) as Uint32,
-                    ));
-                    continue 'running;
+            // a replayed game is already in the high scores, if it is good enough
+            let mut record = None;
+            if let Some(recording) = game.recording() {
+                if let Err(e) = recording.save_here() {
+                    eprintln!("{}", e);
                 }
+                if closed {
+                    // there is no window to ask the name in
+                    if scores.qualifies(game.world().score()) {
+                        save_high_score(&mut scores, &config, game.world(), "");
+                    }
+                    break 'title;
+                }
+                let (place, closed) =
+                    enter_high_score(&mut sdl, &mut scores, &config, game.world());
+                if closed {
+                    break 'title;
+                }
+                record = place;
             }
+            if closed {
+                break 'title;
+            }
+            let mut lines = game.summary();
+            if let Some(place) = record {
+                lines.push(format!("Place {} in the high scores!", place + 1));
+            }
+            lines.push(format!("Press {} to play again,", bindings.confirm.name()));
+            lines.push(format!(
+                "{} to {}.",
+                bindings.quit.name(),
+                if playback.is_some() {
+                    "exit"
+                } else {
+                    "return to the title screen"
+                }
+            ));
+            match game_over(&mut sdl, &bindings, &lines) {
+                AfterGame::Again => continue 'game,
+                AfterGame::Leave if playback.is_none() => continue 'title,
+                AfterGame::Leave | AfterGame::Closed => break 'title,
+            }
+        }
+    }
+}
+
+/// Play the game until it is over or the player quits it. In a versus game the second player
+/// steers the second snake. Returns true if the window was closed.
+pub fn run(sdl: &mut SDLWrapper, bindings: &Bindings, game: &mut Game) -> bool {
+    sdl.layout(game.world().width(), game.world().height());
+    let mut next_frame: Uint64 = 0;
+    let mut pause = Pause::default();
 
-            // Advance
-            match w.step() {
-                Err(StepError::Obstacle) => {
-                    sdl.sounds.obstacle();
-                    quit_msg = "You have hit an obstacle.";
-                    break 'running;
+    'running: loop {
+        // process quit and pause events, and remember the turns for the next steps
+        for event in sdl.poll_events() {
+            let (dir, action) = match event {
+                Event::Quit { .. } => {
+                    game.quit();
+                    return true;
                 }
-                Err(StepError::OutOfField) => {
-                    sdl.sounds.wall();
-                    quit_msg = "You have hit the wall.";
-                    break 'running;
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    let (dir, action) = (bindings.direction(keycode), bindings.action(keycode));
+                    // the first player's keys come first, in case they are the same
+                    match bindings.second_player(keycode) {
+                        Some(steer) if game.players() > 1 && dir.is_none() && action.is_none() => {
+                            if !pause.paused() {
+                                game.steer(1, steer);
+                            }
+                            continue;
+                        }
+                        _ => (dir, action),
+                    }
                 }
-                Err(StepError::SelfHit) => {
-                    sdl.sounds.boom();
-                    quit_msg = "You have hit yourself.";
-                    break 'running;
+                Event::ControllerButtonDown { button, .. } => (
+                    bindings.button_direction(button),
+                    bindings.button_action(button),
+                ),
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => {
+                    pause.focus_lost();
+                    continue;
+                }
+                Event::Window {
+                    win_event:
+                        WindowEvent::FocusGained | WindowEvent::Exposed | WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    pause.exposed();
+                    continue;
                 }
-                Ok(StepOk::AteFood) => {
-                    sdl.sounds.food();
+                _ => continue,
+            };
+            let steer = match (dir, action) {
+                // in the absolute mode the arrows (or the D-pad) point the snake
+                (Some(dir), _) => Steer::Direction(dir),
+                (None, Some(Action::TurnRight)) => Steer::Turn(Turn::Right),
+                (None, Some(Action::TurnLeft)) => Steer::Turn(Turn::Left),
+                (None, Some(Action::Quit)) => {
+                    game.quit();
+                    return false;
                 }
-                Ok(StepOk::AteMystery) => {
-                    sdl.sounds.mystery();
+                (None, Some(Action::Pause)) => {
+                    pause.key();
+                    continue;
                 }
-                Ok(StepOk::Nothing) => {}
+                (None, Some(Action::Fullscreen)) => {
+                    sdl.toggle_fullscreen();
+                    continue;
+                }
+                // taken when the next frame is presented; the paused game is drawn for it
+                (None, Some(Action::Screenshot)) => {
+                    sdl.screenshot();
+                    pause.exposed();
+                    continue;
+                }
+                _ => continue,
+            };
+            if !pause.paused() {
+                game.steer(0, steer);
             }
+        }
 
-            sdl.clear();
-
-            // draw field border
-            for b in 0..=(FIELD_SIZE + 1) {
-                sdl.wall(&0, &b, &0);
-                sdl.wall(&0, &b, &(FIELD_SIZE + 1));
-                sdl.wall(&0, &0, &b);
-                sdl.wall(&0, &(FIELD_SIZE + 1), &b);
+        match pause.update(true) {
+            // only closing the window quits during the countdown
+            PauseChange::Resume => {
+                if !game.resume(sdl) {
+                    return true;
+                }
             }
+            PauseChange::Show => {
+                let resume = format!("Press {} to resume", bindings.pause.name());
+                sdl.draw(game.world());
+                sdl.overlay(&["Paused", resume.as_ref()]);
+            }
+            PauseChange::Nothing => {}
+        }
 
-            let l = w.snake.len() - 1;
-            assert!(
-                l >= 2,
-                "Programming error: the snake cannot be shorter than 3"
-            );
-
-            // draw the snake head
-            let head = w
-                .snake
-                .first()
-                .expect("Programming error: a snake cannot be empty");
-            if head.dir == head.prev_dir {
-                sdl.headstraight(
-                    match head.dir {
-                        Direction::Down => &0,
-                        Direction::Up => &1,
-                        Direction::Left => &2,
-                        Direction::Right => &3,
-                    },
-                    &head.coords.x,
-                    &head.coords.y,
-                );
-            } else {
-                sdl.headturn(
-                    match (&head.dir, &head.prev_dir) {
-                        (&Direction::Left, &Direction::Down) => &0,
-                        (&Direction::Right, &Direction::Down) => &1,
-                        (&Direction::Left, &Direction::Up) => &2,
-                        (&Direction::Right, &Direction::Up) => &3,
-                        (&Direction::Up, &Direction::Right) => &4,
-                        (&Direction::Down, &Direction::Right) => &5,
-                        (&Direction::Up, &Direction::Left) => &6,
-                        (&Direction::Down, &Direction::Left) => &7,
-                        _ => unreachable!("Programming error"),
-                    },
-                    &head.coords.x,
-                    &head.coords.y,
-                );
+        // check if we are at the right moment
+        unsafe {
+            if pause.paused() {
+                SDL_Delay(WAIT as Uint32);
+                continue 'running;
+            }
+            if SDL_GetTicks64() < next_frame {
+                SDL_Delay(min(
+                    WAIT as Uint32,
+                    (next_frame - SDL_GetTicks64()) as Uint32,
+                ));
+                continue 'running;
             }
+        }
 
-            // draw the body of the snake
-            for s in &w.snake[1..l] {
-                sdl.body(
-                    match (&s.dir, &s.prev_dir, &s.even) {
-                        (&Direction::Up, &Direction::Up, &false)
-                        | (&Direction::Down, &Direction::Down, &true) => &0,
-                        (&Direction::Up, &Direction::Up, &true)
-                        | (&Direction::Down, &Direction::Down, &false) => &1,
-                        (&Direction::Left, &Direction::Left, &false)
-                        | (&Direction::Right, &Direction::Right, &true) => &2,
-                        (&Direction::Left, &Direction::Left, &true)
-                        | (&Direction::Right, &Direction::Right, &false) => &3,
-                        (&Direction::Up, &Direction::Right, _)
-                        | (&Direction::Left, &Direction::Down, _) => &4,
-                        (&Direction::Up, &Direction::Left, _)
-                        | (&Direction::Right, &Direction::Down, _) => &5,
-                        (&Direction::Down, &Direction::Right, _)
-                        | (&Direction::Left, &Direction::Up, _) => &6,
-                        (&Direction::Down, &Direction::Left, _)
-                        | (&Direction::Right, &Direction::Up, _) => &7,
-                        _ => unreachable!("Programming error"),
-                    },
-                    &s.coords.x,
-                    &s.coords.y,
-                );
+        // Advance
+        let step = game.step();
+        play_sounds(sdl, &step.results);
+        if game.over() {
+            return false;
+        }
+        if step.next_level {
+            sdl.layout(game.world().width(), game.world().height());
+            sdl.sounds.start();
+            if !game.show_level(sdl) {
+                return true;
             }
+        }
 
-            // draw the tail of the snake
-            let tail = w
-                .snake
-                .last()
-                .expect("Programming error: a snake cannot be empty");
-            sdl.tail(
-                match tail.dir {
-                    Direction::Up => &0,
-                    Direction::Down => &1,
-                    Direction::Left => &2,
-                    Direction::Right => &3,
-                },
-                &tail.coords.x,
-                &tail.coords.y,
-            );
+        sdl.draw(game.world());
+        sdl.present();
 
-            // Draw the things
-            for t in &(w.things) {
-                match t.what {
-                    Thing::Food => {
-                        sdl.food(&t.picture_index, &t.x, &t.y);
-                    }
-                    Thing::Fat => {
-                        sdl.fat(&t.picture_index, &t.x, &t.y);
-                    }
-                    Thing::Lean => {
-                        sdl.lean(&t.picture_index, &t.x, &t.y);
-                    }
-                    Thing::Mystery => {
-                        sdl.mystery(&t.picture_index, &t.x, &t.y);
-                    }
-                    Thing::Obstacle => {
-                        sdl.obstacle(&t.picture_index, &t.x, &t.y);
-                    }
-                }
-            }
+        next_frame = unsafe { SDL_GetTicks64() } + game.world().config().frame_delta;
+    }
+}
 
-            sdl.score(w.score);
-            sdl.present();
+/// The sound of the crash, if a snake died, or else of the best thing eaten
+fn play_sounds(sdl: &mut SDLWrapper, results: &[Result<StepOk, StepError>]) {
+    let ate = |what: StepOk| {
+        results
+            .iter()
+            .any(|r| r.as_ref().is_ok_and(|ok| *ok == what))
+    };
+    match results.iter().find_map(|r| r.as_ref().err()) {
+        Some(StepError::Obstacle) => sdl.sounds.obstacle(),
+        Some(StepError::OutOfField | StepError::Wall) => sdl.sounds.wall(),
+        Some(StepError::SelfHit | StepError::Snake | StepError::HeadOn) => sdl.sounds.boom(),
+        None if ate(StepOk::AteMystery) => sdl.sounds.mystery(),
+        None if ate(StepOk::AteFood) => sdl.sounds.food(),
+        None => {}
+    }
+}
 
-            next_frame = unsafe { SDL_GetTicks64() } + FRAME_DELTA;
-            turned = false;
+/// What the player wants after a game
+pub enum AfterGame {
+    Again,
+    Leave,
+    // the window was closed
+    Closed,
+}
+
+/// Show the messages at the end of a game until the player chooses to play again or to leave, or
+/// closes the window
+pub fn game_over(sdl: &mut SDLWrapper, bindings: &Bindings, lines: &[String]) -> AfterGame {
+    let lines: Vec<&str> = lines.iter().map(|line| line.as_ref()).collect();
+    sdl.messages(lines.clone());
+    loop {
+        for event in sdl.poll_events() {
+            if bindings.screenshots(&event) {
+                // the messages are shown again to be taken
+                sdl.screenshot();
+                sdl.messages(lines.clone());
+            }
+            if matches!(event, Event::Quit { .. }) {
+                return AfterGame::Closed;
+            }
+            if bindings.confirms(&event) {
+                sdl.sounds.start();
+                return AfterGame::Again;
+            }
+            if bindings.quits(&event) {
+                return AfterGame::Leave;
+            }
         }
-        sdl.messages(vec![
-            quit_msg,
-            "Game over.",
-            format!("Score {}.", w.score).as_ref(),
-            "Press SPACE to play again,",
-            "ESC to exit.",
-        ]);
-        loop {
-            for event in sdl.events.poll_iter() {
-                match event {
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Space),
-                        ..
-                    } => {
-                        sdl.sounds.start();
-                        continue 'game;
-                    }
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => {
-                        break 'game;
-                    }
-                    _ => unsafe {
-                        SDL_Delay(100);
-                    },
-                }
+        unsafe {
+            SDL_Delay(100);
+        }
+    }
+}
+
+fn high_scores_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("rnake").join("highscores.toml"))
+}
+
+/// If the game gets into the high scores, ask the player's name and save the table. Returns the
+/// place of the game in the table, and whether the window was closed at the name prompt. A game
+/// whose player gives no name, presses ESC or closes the window is saved without the name.
+fn enter_high_score(
+    sdl: &mut SDLWrapper,
+    scores: &mut HighScores,
+    config: &GameConfig,
+    w: &World,
+) -> (Option<usize>, bool) {
+    if !scores.qualifies(w.score()) {
+        return (None, false);
+    }
+    let (name, closed) = match sdl.text_input("New high score! Your name:") {
+        Typed::Text(name) => (name, false),
+        Typed::Cancelled => (String::new(), false),
+        Typed::Closed => (String::new(), true),
+    };
+    (save_high_score(scores, config, w, &name), closed)
+}
+
+/// Put the game into the high scores under the name, or as "Anonymous" without one, and save the
+/// table. Returns the place of the game in the table.
+fn save_high_score(
+    scores: &mut HighScores,
+    config: &GameConfig,
+    w: &World,
+    name: &str,
+) -> Option<usize> {
+    let name = match name.trim() {
+        "" => "Anonymous".to_string(),
+        name => name.to_string(),
+    };
+    let place = scores.insert(Entry {
+        name,
+        score: w.score(),
+        date: time::today(),
+        mode: menu::mode_name(config),
+        seed: w.seed(),
+    });
+    match high_scores_path() {
+        Some(path) => {
+            if let Err(e) = scores.save(&path) {
+                eprintln!("Cannot save the high scores: {}", e);
             }
         }
+        None => eprintln!("Cannot save the high scores: no data directory"),
     }
+    place
 }
@@ -0,0 +1,301 @@
+use sdl2::controller::Button;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::sys::{SDL_Delay, SDL_GetTicks64, Uint64};
+
+use rnake::autopilot::{Controller, Pathfinder};
+use rnake::config::GameConfig;
+use rnake::game::Game;
+use rnake::highscore::HighScores;
+use rnake::input::Steer;
+use rnake::level::{self, Level};
+use rnake::render::Renderer;
+
+use crate::bindings::{Action, Bindings};
+use crate::sdlwrapper::{SDLWrapper, Typed};
+use crate::MESSAGE_PAUSE;
+
+// the names of the game speeds and the corresponding milliseconds between two steps
+const SPEEDS: [(&str, u64); 4] = [("Slow", 90), ("Normal", 60), ("Fast", 45), ("Insane", 30)];
+// the title screen starts the demo after this many milliseconds without input
+const ATTRACT_DELAY: Uint64 = 30000;
+
+/// What the player wants to do after the title screen
+pub enum Start {
+    Game,
+    // two players on one keyboard
+    Versus,
+    Quit,
+}
+
+/// What the player did in a menu
+enum Choice {
+    // RETURN or SPACE on the item
+    Select(usize),
+    // LEFT (-1) or RIGHT (+1) on the item
+    Change(usize, i32),
+    // ESC
+    Back,
+    // nothing for ATTRACT_DELAY
+    Idle,
+}
+
+/// Show the items and wait until the player chooses something; UP and DOWN (or the D-pad) move
+/// the selection. Besides RETURN and ESC, the keys bound to confirm and quit choose and leave.
+/// With `attract`, gives up waiting after ATTRACT_DELAY without input.
+fn choose(
+    sdl: &mut SDLWrapper,
+    bindings: &Bindings,
+    items: &[String],
+    selected: &mut usize,
+    attract: bool,
+) -> Choice {
+    sdl.menu(items, *selected);
+    let mut idle_since = unsafe { SDL_GetTicks64() };
+    loop {
+        let events = sdl.poll_events();
+        if !events.is_empty() {
+            idle_since = unsafe { SDL_GetTicks64() };
+        } else if attract && unsafe { SDL_GetTicks64() } - idle_since >= ATTRACT_DELAY {
+            return Choice::Idle;
+        }
+        for event in events {
+            let keycode = match event {
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => keycode,
+                // the game controller buttons work as the corresponding keys
+                Event::ControllerButtonDown { button, .. } => match button {
+                    Button::DPadUp => Keycode::Up,
+                    Button::DPadDown => Keycode::Down,
+                    Button::DPadLeft => Keycode::Left,
+                    Button::DPadRight => Keycode::Right,
+                    Button::A | Button::Start => Keycode::Return,
+                    Button::B | Button::Back => Keycode::Escape,
+                    _ => continue,
+                },
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..) | WindowEvent::Exposed,
+                    ..
+                } => {
+                    sdl.menu(items, *selected);
+                    continue;
+                }
+                Event::Quit { .. } => return Choice::Back,
+                _ => continue,
+            };
+            match keycode {
+                Keycode::Up => {
+                    *selected = (*selected + items.len() - 1) % items.len();
+                    sdl.menu(items, *selected);
+                }
+                Keycode::Down => {
+                    *selected = (*selected + 1) % items.len();
+                    sdl.menu(items, *selected);
+                }
+                Keycode::Left => return Choice::Change(*selected, -1),
+                Keycode::Right => return Choice::Change(*selected, 1),
+                Keycode::Return | Keycode::KpEnter | Keycode::Space => {
+                    return Choice::Select(*selected)
+                }
+                Keycode::Escape => return Choice::Back,
+                keycode => match bindings.action(keycode) {
+                    Some(Action::Confirm) => return Choice::Select(*selected),
+                    Some(Action::Quit) => return Choice::Back,
+                    Some(Action::Fullscreen) => sdl.toggle_fullscreen(),
+                    _ => {}
+                },
+            }
+        }
+        unsafe {
+            SDL_Delay(20);
+        }
+    }
+}
+
+fn speed_name(frame_delta: u64) -> String {
+    match SPEEDS.iter().find(|(_, delta)| *delta == frame_delta) {
+        Some((name, _)) => name.to_string(),
+        None => format!("{} ms", frame_delta),
+    }
+}
+
+/// How the game is played, for the high scores
+pub fn mode_name(config: &GameConfig) -> String {
+    let speed = speed_name(config.frame_delta);
+    if config.wrap {
+        format!("{}, wrap", speed)
+    } else {
+        speed
+    }
+}
+
+/// The speed preset after (or before, for a negative step) the current one
+fn next_speed(frame_delta: u64, step: i32) -> u64 {
+    let len = SPEEDS.len() as i32;
+    let current = SPEEDS
+        .iter()
+        .position(|(_, delta)| *delta == frame_delta)
+        // a speed which is not a preset is treated as the first one
+        .map_or(0, |idx| idx as i32);
+    SPEEDS[(current + step).rem_euclid(len) as usize].1
+}
+
+/// The title screen. Lets the player choose the speed, the first level and the options (including
+/// the keys), and shows the demo when the player does nothing; returns what to play, if
+/// anything. A password opens its level and the ones before it, up to `unlocked`, which LEFT
+/// and RIGHT then choose among.
+pub fn title(
+    sdl: &mut SDLWrapper,
+    config: &mut GameConfig,
+    bindings: &mut Bindings,
+    levels: &[Level],
+    first_level: &mut usize,
+    unlocked: &mut usize,
+    scores: &HighScores,
+) -> Start {
+    let mut selected = 0;
+    loop {
+        let items = vec![
+            "Start".to_string(),
+            "Versus".to_string(),
+            format!("Speed: {}", speed_name(config.frame_delta)),
+            format!("Level: {}", levels[*first_level].name),
+            "Options".to_string(),
+            "High scores".to_string(),
+            "Quit".to_string(),
+        ];
+        match choose(sdl, bindings, &items, &mut selected, true) {
+            Choice::Select(0) => return Start::Game,
+            Choice::Select(1) => return Start::Versus,
+            Choice::Select(2) => config.frame_delta = next_speed(config.frame_delta, 1),
+            Choice::Change(2, step) => config.frame_delta = next_speed(config.frame_delta, step),
+            Choice::Select(3) => {
+                let password = match sdl.text_input("Level password:") {
+                    Typed::Text(password) => password,
+                    Typed::Cancelled => continue,
+                    Typed::Closed => return Start::Quit,
+                };
+                match level::find_by_password(levels, &password) {
+                    Some(idx) => {
+                        *first_level = idx;
+                        *unlocked = (*unlocked).max(idx);
+                    }
+                    // no password starts the game from the beginning
+                    None if password.trim().is_empty() => *first_level = 0,
+                    None => {
+                        sdl.messages(vec!["Wrong password."]);
+                        unsafe {
+                            SDL_Delay(MESSAGE_PAUSE);
+                        }
+                    }
+                }
+            }
+            Choice::Change(3, step) => {
+                *first_level = first_level
+                    .saturating_add_signed(step as isize)
+                    .min(*unlocked)
+            }
+            Choice::Select(4) => options(sdl, config, bindings),
+            Choice::Select(5) => high_scores(sdl, bindings, scores),
+            Choice::Select(6) | Choice::Back => return Start::Quit,
+            Choice::Idle if !demo(sdl, config, levels, *first_level) => return Start::Quit,
+            _ => {}
+        }
+    }
+}
+
+/// The attract mode: the autopilot plays from the chosen level until it dies or the player does
+/// anything; returns false if the player wants to quit
+fn demo(sdl: &mut SDLWrapper, config: &GameConfig, levels: &[Level], first_level: usize) -> bool {
+    let mut game = Game::new(config, levels, first_level, rand::random());
+    let mut autopilot = Pathfinder;
+    sdl.layout(game.world().width(), game.world().height());
+    loop {
+        for event in sdl.poll_events() {
+            match event {
+                Event::Quit { .. } => return false,
+                Event::KeyDown { .. } | Event::ControllerButtonDown { .. } => return true,
+                _ => {}
+            }
+        }
+
+        sdl.draw(game.world());
+        sdl.caption(&["Demo", "Press any key"]);
+        unsafe {
+            SDL_Delay(config.frame_delta as u32);
+        }
+
+        if let Some(turn) = autopilot.decide(game.world()) {
+            game.steer(0, Steer::Turn(turn));
+        }
+        let step = game.step();
+        if game.over() {
+            return true;
+        }
+        if step.next_level {
+            sdl.layout(game.world().width(), game.world().height());
+        }
+    }
+}
+
+fn high_scores(sdl: &mut SDLWrapper, bindings: &Bindings, scores: &HighScores) {
+    let mut lines: Vec<String> = scores
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(idx, e)| format!("{}. {} {} {} {}", idx + 1, e.name, e.score, e.mode, e.date))
+        .collect();
+    if lines.is_empty() {
+        lines.push("No high scores yet.".to_string());
+    }
+    lines.push("Back".to_string());
+    // whatever is chosen, go back
+    let mut selected = lines.len() - 1;
+    choose(sdl, bindings, &lines, &mut selected, false);
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn options(sdl: &mut SDLWrapper, config: &mut GameConfig, bindings: &mut Bindings) {
+    let mut selected = 0;
+    let saved = bindings.clone();
+    loop {
+        let mut items = vec![
+            format!("Wrap around: {}", on_off(config.wrap)),
+            format!("Controls: {}", bindings.profile().unwrap_or("custom")),
+            format!("Steering: {}", bindings.control.name()),
+        ];
+        // one item per action, after the ones above
+        let first_key = items.len();
+        for action in Action::ALL {
+            items.push(format!("{}: {}", action, bindings.key(action).name()));
+        }
+        items.push("Back".to_string());
+        match choose(sdl, bindings, &items, &mut selected, false) {
+            Choice::Select(0) | Choice::Change(0, _) => config.wrap = !config.wrap,
+            Choice::Select(1) | Choice::Change(1, _) => *bindings = bindings.next_profile(),
+            Choice::Select(2) | Choice::Change(2, _) => {
+                bindings.control = bindings.control.toggle()
+            }
+            Choice::Select(idx) if idx >= first_key && idx < first_key + Action::ALL.len() => {
+                let action = Action::ALL[idx - first_key];
+                if let Some(key) = sdl.read_key(format!("Press a key for {}", action).as_ref()) {
+                    bindings.set_key(action, key);
+                }
+            }
+            Choice::Select(_) | Choice::Back => break,
+            _ => {}
+        }
+    }
+    if *bindings != saved {
+        bindings.save();
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use rnake::cli::{self, ConfigOptions};
+
+/// Command line options
+#[derive(Default)]
+pub struct Options {
+    pub game: ConfigOptions,
+    // play the game with this seed instead of a random one
+    pub seed: Option<u64>,
+    // play back this replay file instead of reading the keyboard
+    pub replay: Option<PathBuf>,
+    // play in a window of this size instead of the full screen
+    pub window: Option<(u32, u32)>,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = String> + 'static) -> Result<Self, String> {
+        let mut options = Options::default();
+        cli::parse(args, |name, args| {
+            match name {
+                "--seed" => options.seed = Some(args.parsed("seed")?),
+                "--replay" => options.replay = Some(PathBuf::from(args.value()?)),
+                "--window" => {
+                    let v = args.value()?;
+                    let bad = || format!("Bad window size '{}', expected e.g. 1280x720", v);
+                    let (width, height) = v.split_once('x').ok_or_else(bad)?;
+                    options.window = Some((
+                        width.parse().map_err(|_| bad())?,
+                        height.parse().map_err(|_| bad())?,
+                    ));
+                }
+                _ => return options.game.option(name, args),
+            }
+            Ok(true)
+        })?;
+        Ok(options)
+    }
+}
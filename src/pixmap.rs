@@ -0,0 +1,184 @@
+//! The pictures of the game, rendered from the SVG images in `images/` to pixmaps in memory,
+//! and a renderer which draws the world on a pixmap without a window.
+
+use std::collections::HashMap;
+
+use resvg::tiny_skia::{Color, Pixmap, PixmapPaint, Transform};
+use resvg::usvg::TreeParsing;
+use resvg::Tree;
+
+use crate::render::{self, Renderer};
+use crate::world::World;
+
+/// This macro creates resvg::tiny_skia::Pixmap of the given size, renders on it image from
+/// images/, and pushes the pixmap to a vector in a given hashmap.
+macro_rules! load_one_image {
+    ($cell:expr, $pixmaps:ident, $name:ident $num:literal) => {{
+        let tree = resvg::usvg::Tree::from_str(
+            include_str!(concat!(
+                "images/",
+                stringify!($name),
+                "0",
+                stringify!($num),
+                ".svg"
+            )),
+            &resvg::usvg::Options::default(),
+        )
+        .expect("Should be able to parse SVG tree");
+        let rtree = Tree::from_usvg(&tree);
+        let cell = *$cell;
+        let mut pixmap = Pixmap::new(cell, cell).expect("Should be able to create pixmap");
+        let scale = cell as f32 / f32::max(rtree.size.width(), rtree.size.height());
+        rtree.render(
+            // no, this does not make sense
+            resvg::tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+        $pixmaps
+            .entry(stringify!($name).to_string())
+            .or_insert(vec![])
+            .insert(0, pixmap);
+    }};
+}
+
+/// This macro loads the given amount of images
+macro_rules! load_images_rec {
+    ($cell:expr, $pixmaps:ident, $name:ident, 1) => {
+        load_one_image!($cell, $pixmaps, $name 1);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 2) => {
+        load_one_image!($cell, $pixmaps, $name 2);
+        load_images_rec!($cell, $pixmaps, $name, 1);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 3) => {
+        load_one_image!($cell, $pixmaps, $name 3);
+        load_images_rec!($cell, $pixmaps, $name, 2);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 4) => {
+        load_one_image!($cell, $pixmaps, $name 4);
+        load_images_rec!($cell, $pixmaps, $name, 3);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 5) => {
+        load_one_image!($cell, $pixmaps, $name 5);
+        load_images_rec!($cell, $pixmaps, $name, 4);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 6) => {
+        load_one_image!($cell, $pixmaps, $name 6);
+        load_images_rec!($cell, $pixmaps, $name, 5);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 7) => {
+        load_one_image!($cell, $pixmaps, $name 7);
+        load_images_rec!($cell, $pixmaps, $name, 6);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 8) => {
+        load_one_image!($cell, $pixmaps, $name 8);
+        load_images_rec!($cell, $pixmaps, $name, 7);
+    };
+    ($cell:expr, $pixmaps:ident, $name:ident, 9) => {
+        load_one_image!($cell, $pixmaps, $name 9);
+        load_images_rec!($cell, $pixmaps, $name, 8);
+    };
+}
+
+/// This macro does the following:
+/// - defines create_pixmaps() which loads pixmaps, and makes the pictures of the second snake
+///   from the ones of the first
+/// - defines SPRITES, the names of the images with how many of each there are
+macro_rules! load_images {
+    ($($name:ident $count:tt),*; $($variant:ident = $original:ident $vcount:tt),*) => {
+        /// Render all the images to pixmaps of the given size, by their names; the images of
+        /// one name are in the order of their numbers
+        pub fn create_pixmaps(cell_size: &u32) -> HashMap<String, Vec<Pixmap>> {
+            let mut pixmaps = HashMap::new();
+            $(load_images_rec!(cell_size, pixmaps, $name, $count);)*
+            $(
+                let variant = tinted(&pixmaps[stringify!($original)], SECOND_SNAKE);
+                pixmaps.insert(stringify!($variant).to_string(), variant);
+            )*
+            pixmaps
+        }
+        pub const SPRITES: &[(&str, usize)] = &[
+            $((stringify!($name), $count),)*
+            $((stringify!($variant), $vcount)),*
+        ];
+    };
+}
+
+load_images!(body 8, fat 3, food 3, headturn 8, headstraight 4, lean 3, mystery 4, obstacle 3, portal 1, tail 4, wall 1;
+             body2 = body 8, headturn2 = headturn 8, headstraight2 = headstraight 4, tail2 = tail 4);
+
+// the grey of the snake becomes this color, as parts of red, green and blue, for the second player
+const SECOND_SNAKE: [f32; 3] = [0.35, 1.0, 0.45];
+
+/// The pictures in another color: the light grey parts are tinted, and the dark eyes and the red
+/// tongue stay as they are
+fn tinted(pixmaps: &[Pixmap], tint: [f32; 3]) -> Vec<Pixmap> {
+    pixmaps
+        .iter()
+        .map(|pixmap| {
+            let mut pixmap = pixmap.clone();
+            // the colors are premultiplied by the alpha, which does not change whether they
+            // are grey, and making them darker keeps them valid
+            for pixel in pixmap.data_mut().chunks_exact_mut(4) {
+                let (min, max) = (pixel[..3].iter().min(), pixel[..3].iter().max());
+                let grey = max.zip(min).is_some_and(|(max, min)| max - min < 16);
+                if grey && pixel[0] as u32 * 10 > pixel[3] as u32 * 3 {
+                    for (channel, part) in pixel[..3].iter_mut().zip(tint) {
+                        *channel = (*channel as f32 * part) as u8;
+                    }
+                }
+            }
+            pixmap
+        })
+        .collect()
+}
+
+/// Draws the world on a pixmap as large as the field with the wall around it, e.g. for
+/// screenshots. There is no font, so the score is not drawn.
+pub struct PixmapRenderer {
+    cell: u32,
+    pixmaps: HashMap<String, Vec<Pixmap>>,
+    // the last frame drawn
+    frame: Option<Pixmap>,
+}
+
+impl PixmapRenderer {
+    /// Render the pictures for the cells of the given size, in pixels
+    pub fn new(cell: u32) -> Self {
+        assert!(cell > 0, "Programming error: the cells cannot be empty");
+        PixmapRenderer {
+            cell,
+            pixmaps: create_pixmaps(&cell),
+            frame: None,
+        }
+    }
+
+    /// The last frame drawn, if any
+    pub fn frame(&self) -> Option<&Pixmap> {
+        self.frame.as_ref()
+    }
+}
+
+impl Renderer for PixmapRenderer {
+    fn draw(&mut self, w: &World) {
+        let cell = self.cell;
+        let (width, height) = ((w.width() + 2) * cell, (w.height() + 2) * cell);
+        // the frames of one level are all the same size
+        let mut frame = match self.frame.take() {
+            Some(frame) if frame.width() == width && frame.height() == height => frame,
+            _ => Pixmap::new(width, height).expect("Should be able to create pixmap"),
+        };
+        frame.fill(Color::BLACK);
+        for s in render::background(w).iter().chain(&render::foreground(w)) {
+            frame.draw_pixmap(
+                (s.x * cell) as i32,
+                (s.y * cell) as i32,
+                self.pixmaps[s.name][s.idx].as_ref(),
+                &PixmapPaint::default(),
+                Transform::identity(),
+                None,
+            );
+        }
+        self.frame = Some(frame);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::world::{Direction, SnakeCell, Thing, World};
+
+/// Something which shows the world to the player: a window, a terminal, an image.
+///
+/// The frontends decide how the world looks; the ones drawing the pictures from `images/` get
+/// them from [`background`] and [`foreground`]. The main loop only tells them to draw the world
+/// after every step.
+pub trait Renderer {
+    /// Draw the whole field with everything on it, and the scores
+    fn draw(&mut self, world: &World);
+}
+
+/// A picture in a cell of the field: `name` is one of the pictures in `images/`, e.g. `food`,
+/// and `idx` counts its variants from 0. The wall around the field is at 0 and at the field
+/// size plus 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub name: &'static str,
+    pub idx: usize,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Sprite {
+    fn new(name: &'static str, idx: usize, x: u32, y: u32) -> Self {
+        Sprite { name, idx, x, y }
+    }
+}
+
+/// What does not change while the level is played: the border and the walls
+pub fn background(w: &World) -> Vec<Sprite> {
+    let (field_width, field_height) = (w.width(), w.height());
+    // without the wall the snake goes through portals to the other side of the field
+    let border = if w.config().wrap { "portal" } else { "wall" };
+    let mut sprites = vec![];
+    for b in 1..=field_width {
+        sprites.push(Sprite::new(border, 0, b, 0));
+        sprites.push(Sprite::new(border, 0, b, field_height + 1));
+    }
+    for b in 1..=field_height {
+        sprites.push(Sprite::new(border, 0, 0, b));
+        sprites.push(Sprite::new(border, 0, field_width + 1, b));
+    }
+    for (x, y) in [
+        (0, 0),
+        (field_width + 1, 0),
+        (0, field_height + 1),
+        (field_width + 1, field_height + 1),
+    ] {
+        sprites.push(Sprite::new("wall", 0, x, y));
+    }
+
+    // the walls of the level never move
+    for t in w.things.iter().filter(|t| t.what == Thing::Wall) {
+        sprites.push(Sprite::new("wall", t.picture_index, t.x, t.y));
+    }
+    sprites
+}
+
+/// The pictures of the snakes of the players: the straight head, the turning head, the body and
+/// the tail; every player's snake has its own colors
+const SNAKE_PICTURES: [[&str; 4]; 2] = [
+    ["headstraight", "headturn", "body", "tail"],
+    ["headstraight2", "headturn2", "body2", "tail2"],
+];
+
+/// The snakes and the things on the field, drawn over the background
+pub fn foreground(w: &World) -> Vec<Sprite> {
+    let mut sprites = vec![];
+    for (player, snake) in w.snakes.iter().enumerate() {
+        snake_sprites(
+            &snake.cells,
+            SNAKE_PICTURES[player % SNAKE_PICTURES.len()],
+            &mut sprites,
+        );
+    }
+
+    // the things
+    for t in &w.things {
+        let name = match t.what {
+            Thing::Food => "food",
+            Thing::Fat => "fat",
+            Thing::Lean => "lean",
+            Thing::Mystery => "mystery",
+            Thing::Obstacle => "obstacle",
+            // drawn with the background
+            Thing::Wall => continue,
+        };
+        sprites.push(Sprite::new(name, t.picture_index, t.x, t.y));
+    }
+    sprites
+}
+
+fn snake_sprites(snake: &[SnakeCell], pictures: [&'static str; 4], sprites: &mut Vec<Sprite>) {
+    let [head_straight, head_turn, body, tail] = pictures;
+    let l = snake.len() - 1;
+    assert!(
+        l >= 2,
+        "Programming error: the snake cannot be shorter than 3"
+    );
+
+    // the snake head
+    let head = snake
+        .first()
+        .expect("Programming error: a snake cannot be empty");
+    sprites.push(if head.dir == head.prev_dir {
+        Sprite::new(
+            head_straight,
+            head_straight_index(&head.dir),
+            head.coords.x,
+            head.coords.y,
+        )
+    } else {
+        Sprite::new(
+            head_turn,
+            head_turn_index(head),
+            head.coords.x,
+            head.coords.y,
+        )
+    });
+
+    // the body of the snake
+    for s in &snake[1..l] {
+        sprites.push(Sprite::new(body, body_index(s), s.coords.x, s.coords.y));
+    }
+
+    // the tail of the snake
+    let tail_cell = snake
+        .last()
+        .expect("Programming error: a snake cannot be empty");
+    sprites.push(Sprite::new(
+        tail,
+        tail_index(&tail_cell.dir),
+        tail_cell.coords.x,
+        tail_cell.coords.y,
+    ));
+}
+
+// the indexes of the pictures of the snake, see images/
+
+fn head_straight_index(dir: &Direction) -> usize {
+    match dir {
+        Direction::Down => 0,
+        Direction::Up => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
+
+fn head_turn_index(head: &SnakeCell) -> usize {
+    match (&head.dir, &head.prev_dir) {
+        (Direction::Left, Direction::Down) => 0,
+        (Direction::Right, Direction::Down) => 1,
+        (Direction::Left, Direction::Up) => 2,
+        (Direction::Right, Direction::Up) => 3,
+        (Direction::Up, Direction::Right) => 4,
+        (Direction::Down, Direction::Right) => 5,
+        (Direction::Up, Direction::Left) => 6,
+        (Direction::Down, Direction::Left) => 7,
+        _ => unreachable!("Programming error: the head does not turn"),
+    }
+}
+
+fn body_index(s: &SnakeCell) -> usize {
+    match (&s.dir, &s.prev_dir, s.even) {
+        (Direction::Up, Direction::Up, false) | (Direction::Down, Direction::Down, true) => 0,
+        (Direction::Up, Direction::Up, true) | (Direction::Down, Direction::Down, false) => 1,
+        (Direction::Left, Direction::Left, false) | (Direction::Right, Direction::Right, true) => 2,
+        (Direction::Left, Direction::Left, true) | (Direction::Right, Direction::Right, false) => 3,
+        (Direction::Up, Direction::Right, _) | (Direction::Left, Direction::Down, _) => 4,
+        (Direction::Up, Direction::Left, _) | (Direction::Right, Direction::Down, _) => 5,
+        (Direction::Down, Direction::Right, _) | (Direction::Left, Direction::Up, _) => 6,
+        (Direction::Down, Direction::Left, _) | (Direction::Right, Direction::Up, _) => 7,
+        _ => unreachable!("Programming error: the snake cannot turn back"),
+    }
+}
+
+fn tail_index(dir: &Direction) -> usize {
+    match dir {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+    }
+}
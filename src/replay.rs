@@ -0,0 +1,268 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ConfigError, GameConfig};
+use crate::world::Turn;
+
+const HEADER: &str = "rnake-replay 1";
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    // the file the replay could not be saved to
+    Save(PathBuf, io::Error),
+    // the line number (counting from 1) and what is wrong with it
+    Format(usize, String),
+    Config(ConfigError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "cannot read replay: {}", e),
+            ReplayError::Save(path, e) => {
+                write!(f, "cannot save replay to {}: {}", path.display(), e)
+            }
+            ReplayError::Format(line, msg) => write!(f, "bad replay, line {}: {}", line, msg),
+            ReplayError::Config(e) => write!(f, "bad replay: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+/// The seed, the configuration and the first level of a game and the turns made in it, enough
+/// to play the game again exactly.
+///
+/// The file is plain text: the header line, `seed <seed>`, `level <index in the campaign>`, one
+/// `config <key> = <value>` line per configuration value, then one `<tick> <L|R>` line per
+/// turn, where the tick is `World::tick()` at the moment of the turn, and `end <tick>`, the tick
+/// at which the game was over. The snake turns at most once per step, so every turn has a tick of
+/// its own.
+#[derive(Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: GameConfig,
+    pub level: usize,
+    // sorted by tick, at most one per tick
+    turns: Vec<(u64, Turn)>,
+    // the tick at which the game was over
+    pub end: Option<u64>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, config: GameConfig, level: usize) -> Self {
+        Replay {
+            seed,
+            config,
+            level,
+            turns: vec![],
+            end: None,
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, turn: Turn) {
+        assert!(
+            self.turns.last().is_none_or(|(t, _)| *t < tick),
+            "Programming error: turns must be recorded in order, one per tick"
+        );
+        self.turns.push((tick, turn));
+    }
+
+    /// Remember the tick at which the game was over, e.g. the player quit it
+    pub fn finish(&mut self, tick: u64) {
+        self.end = Some(tick);
+    }
+
+    /// The turn made at the given tick, if any
+    pub fn turn_at(&self, tick: u64) -> Option<&Turn> {
+        self.turns
+            .binary_search_by_key(&tick, |(t, _)| *t)
+            .ok()
+            .map(|idx| &self.turns[idx].1)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// The replay in the format of the file
+    pub fn to_text(&self) -> String {
+        let mut out = format!("{}\nseed {}\nlevel {}\n", HEADER, self.seed, self.level);
+        for line in self.config.to_toml().lines() {
+            out.push_str(&format!("config {}\n", line));
+        }
+        for (tick, turn) in &self.turns {
+            let code = match turn {
+                Turn::Left => 'L',
+                Turn::Right => 'R',
+            };
+            out.push_str(&format!("{} {}\n", tick, code));
+        }
+        if let Some(end) = self.end {
+            out.push_str(&format!("end {}\n", end));
+        }
+        out
+    }
+
+    /// Save the replay of a finished game in the current directory as `rnake-<seed>.replay`.
+    /// Returns the path of the file.
+    pub fn save_here(&self) -> Result<PathBuf, ReplayError> {
+        let path = PathBuf::from(format!("rnake-{}.replay", self.seed));
+        match self.save(&path) {
+            Ok(()) => Ok(path),
+            Err(e) => Err(ReplayError::Save(path, e)),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::from_text(&fs::read_to_string(path)?)
+    }
+
+    /// The replay from the text of a file
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines().enumerate().map(|(n, l)| (n + 1, l.trim()));
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(ReplayError::Format(1, "not an rnake replay".to_string())),
+        }
+        // the line which is missing comes after the last one
+        let missing = text.lines().count() + 1;
+        let mut number = |key: &str| match lines.next() {
+            Some((n, line)) => line
+                .strip_prefix(key)
+                .and_then(|s| s.trim().parse().ok())
+                .ok_or_else(|| ReplayError::Format(n, format!("expected '{} <number>'", key))),
+            None => Err(ReplayError::Format(missing, format!("no {}", key))),
+        };
+        let seed = number("seed")?;
+        let level = number("level")? as usize;
+        let mut config = String::new();
+        let mut turns: Vec<(u64, Turn)> = vec![];
+        let mut end = None;
+        for (n, line) in lines.filter(|(_, l)| !l.is_empty()) {
+            if let Some(value) = line.strip_prefix("config ") {
+                config.push_str(value);
+                config.push('\n');
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("end ") {
+                let tick = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| ReplayError::Format(n, "expected 'end <number>'".to_string()))?;
+                end = Some(tick);
+                continue;
+            }
+            let bad = || ReplayError::Format(n, "expected '<tick> <L|R>'".to_string());
+            let (tick, code) = line.split_once(' ').ok_or_else(bad)?;
+            let tick: u64 = tick.parse().map_err(|_| bad())?;
+            let turn = match code {
+                "L" => Turn::Left,
+                "R" => Turn::Right,
+                _ => return Err(bad()),
+            };
+            if turns.last().is_some_and(|(t, _)| *t >= tick) {
+                return Err(ReplayError::Format(
+                    n,
+                    "turns must be in order, one per tick".to_string(),
+                ));
+            }
+            turns.push((tick, turn));
+        }
+        let config = config
+            .parse::<toml::Table>()
+            .map_err(|e| ConfigError::Parse(e.to_string()))
+            .and_then(GameConfig::from_table)
+            .map_err(ReplayError::Config)?;
+        Ok(Replay {
+            seed,
+            config,
+            level,
+            turns,
+            end,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::input::Steer;
+    use crate::level;
+
+    #[test]
+    fn the_text_reads_back() {
+        let config = GameConfig {
+            wrap: true,
+            ..GameConfig::default()
+        };
+        let mut replay = Replay::new(42, config, 2);
+        replay.record(3, Turn::Left);
+        replay.record(10, Turn::Left);
+        replay.finish(17);
+        let read = Replay::from_text(&replay.to_text()).expect("Should be able to read the replay");
+        assert_eq!((read.seed, read.level, read.end), (42, 2, Some(17)));
+        assert_eq!(read.turns, replay.turns);
+        assert_eq!(read.config.to_toml(), replay.config.to_toml());
+    }
+
+    #[test]
+    fn bad_lines_are_numbered() {
+        let text = format!("{}\nseed 1\nlevel 0\n3 L\n2 R\n", HEADER);
+        match Replay::from_text(&text) {
+            Err(ReplayError::Format(5, _)) => {}
+            other => panic!("expected an error on line 5, got {:?}", other),
+        }
+        // the snake cannot turn twice in one step
+        let text = format!("{}\nseed 1\nlevel 0\n3 L\n3 L\n", HEADER);
+        match Replay::from_text(&text) {
+            Err(ReplayError::Format(5, msg)) => {
+                assert_eq!(msg, "turns must be in order, one per tick")
+            }
+            other => panic!("expected an error on line 5, got {:?}", other),
+        }
+        match Replay::from_text(&format!("{}\nseed 1\n", HEADER)) {
+            Err(ReplayError::Format(3, msg)) => assert_eq!(msg, "no level"),
+            other => panic!("expected an error on line 3, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_saved_game_plays_back_the_same() {
+        let config = GameConfig::default();
+        let levels = level::campaign(&config);
+        let mut game = Game::new(&config, &levels, 0, 11);
+        while !game.over() {
+            // turn now and then, twice in a row to get back into the field
+            if game.world().tick() % 7 < 2 {
+                game.steer(0, Steer::Turn(Turn::Right));
+            }
+            game.step();
+        }
+        let recording = game.recording().expect("Should be able to record the game");
+        assert_eq!(recording.end, Some(game.world().tick()));
+
+        let replay =
+            Replay::from_text(&recording.to_text()).expect("Should be able to read the replay");
+        let mut replayed = Game::replay(&replay.config, &levels, &replay);
+        while !replayed.over() {
+            // the turns come from the replay only
+            replayed.steer(0, Steer::Turn(Turn::Left));
+            replayed.step();
+        }
+        assert_eq!(replayed.world().tick(), game.world().tick());
+        assert_eq!(replayed.world().score(), game.world().score());
+        // a replay is not recorded again
+        assert!(replayed.recording().is_none());
+    }
+}
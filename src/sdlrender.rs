@@ -0,0 +1,45 @@
+use sdl2::event::Event;
+
+use rnake::game::Screen;
+use rnake::render::{self, Renderer};
+use rnake::world::World;
+
+use crate::sdlwrapper::SDLWrapper;
+
+impl Renderer for SDLWrapper<'_> {
+    /// Draw the frame; the caller presents it, possibly with an overlay
+    fn draw(&mut self, w: &World) {
+        if self.needs_background() {
+            self.begin_background();
+            for s in render::background(w) {
+                self.sprite(s.name, s.idx, s.x, s.y);
+            }
+            self.end_background();
+        }
+        self.draw_background();
+
+        for s in render::foreground(w) {
+            self.sprite(s.name, s.idx, s.x, s.y);
+        }
+
+        let scores: Vec<u32> = w.snakes.iter().map(|s| s.score).collect();
+        self.scores(&scores);
+    }
+}
+
+impl Screen for SDLWrapper<'_> {
+    fn overlay(&mut self, lines: &[&str]) {
+        SDLWrapper::overlay(self, lines);
+    }
+
+    fn messages(&mut self, lines: &[&str]) {
+        SDLWrapper::messages(self, lines.to_vec());
+    }
+
+    /// Only closing the window quits here; the keys are all thrown away
+    fn skip_input(&mut self) -> bool {
+        self.poll_events()
+            .iter()
+            .any(|event| matches!(event, Event::Quit { .. }))
+    }
+}
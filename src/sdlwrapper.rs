@@ -1,16 +1,24 @@
-use std::cmp::min;
-use std::collections::HashMap;
+use std::cmp::{max, min};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::thread::{self, JoinHandle};
 
-use resvg::usvg::TreeParsing;
-use resvg::Tree;
+use sdl2::controller::{Button, GameController};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::Rect;
-use sdl2::render::{Canvas, TextureAccess, TextureQuery};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator, TextureQuery};
 use sdl2::rwops::RWops;
-use sdl2::sys::{SDL_ShowCursor, SDL_DISABLE};
+use sdl2::sys::{SDL_Delay, SDL_ShowCursor, SDL_DISABLE, SDL_ENABLE};
 use sdl2::ttf::{Font, Sdl2TtfContext};
-use sdl2::video::Window;
-use sdl2::{pixels::Color, EventPump};
+use sdl2::video::{FullscreenType, Window, WindowContext};
+use sdl2::{pixels::Color, EventPump, GameControllerSubsystem, VideoSubsystem};
+
+use resvg::tiny_skia::{IntSize, Pixmap};
+use rnake::config::MIN_WINDOW_SIZE;
+use rnake::pixmap::{create_pixmaps, SPRITES};
+use rnake::time;
 
 use crate::sound::{Player, Sounds};
 
@@ -21,142 +29,153 @@ macro_rules! rect {
     };
 }
 
-/// This macro creates resvg::tiny_skia::Pixmap of the given size, renders on it image from
-/// images/, and pushes the pixmap to a vector in a given hashmap.
-macro_rules! load_one_image {
-    ($cell:expr, $pixmaps:ident, $name:ident $num:literal) => {{
-        let tree = resvg::usvg::Tree::from_str(
-            include_str!(concat!(
-                "images/",
-                stringify!($name),
-                "0",
-                stringify!($num),
-                ".svg"
-            )),
-            &resvg::usvg::Options::default(),
-        )
-        .expect("Should be able to parse SVG tree");
-        let rtree = Tree::from_usvg(&tree);
-        let cell = *$cell;
-        let mut pixmap =
-            resvg::tiny_skia::Pixmap::new(cell, cell).expect("Should be able to create pixmap");
-        let scale = cell as f32 / f32::max(rtree.size.width(), rtree.size.height());
-        rtree.render(
-            // no, this does not make sense
-            resvg::tiny_skia::Transform::from_scale(scale, scale),
-            &mut pixmap.as_mut(),
-        );
-        $pixmaps
-            .entry(stringify!($name).to_string())
-            .or_insert(vec![])
-            .insert(0, pixmap);
-    }};
+const LINE_INTERVAL: u32 = 10;
+// the longest text the player can type in
+const MAX_INPUT: usize = 16;
+// the width of the area on the right of the field where the score is shown
+const SCORE_WIDTH: u32 = 150;
+// the texture atlas has a row of this many images for every sprite name
+const ATLAS_COLUMNS: u32 = 8;
+// how much the overlay darkens what is under it, from 0 (not at all) to 255 (black)
+const OVERLAY_ALPHA: u8 = 160;
+
+/// How the player finished typing in a text
+pub enum Typed {
+    // RETURN with the text typed in
+    Text(String),
+    // ESC
+    Cancelled,
+    // the window was closed
+    Closed,
 }
 
-/// This macro loads the given amount of images
-macro_rules! load_images_rec {
-    ($cell:expr, $pixmaps:ident, $name:ident, 1) => {
-        load_one_image!($cell, $pixmaps, $name 1);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 2) => {
-        load_one_image!($cell, $pixmaps, $name 2);
-        load_images_rec!($cell, $pixmaps, $name, 1);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 3) => {
-        load_one_image!($cell, $pixmaps, $name 3);
-        load_images_rec!($cell, $pixmaps, $name, 2);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 4) => {
-        load_one_image!($cell, $pixmaps, $name 4);
-        load_images_rec!($cell, $pixmaps, $name, 3);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 5) => {
-        load_one_image!($cell, $pixmaps, $name 5);
-        load_images_rec!($cell, $pixmaps, $name, 4);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 6) => {
-        load_one_image!($cell, $pixmaps, $name 6);
-        load_images_rec!($cell, $pixmaps, $name, 5);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 7) => {
-        load_one_image!($cell, $pixmaps, $name 7);
-        load_images_rec!($cell, $pixmaps, $name, 6);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 8) => {
-        load_one_image!($cell, $pixmaps, $name 8);
-        load_images_rec!($cell, $pixmaps, $name, 7);
-    };
-    ($cell:expr, $pixmaps:ident, $name:ident, 9) => {
-        load_one_image!($cell, $pixmaps, $name 9);
-        load_images_rec!($cell, $pixmaps, $name, 8);
-    };
+/// An image from the atlas in a cell of the field: the row and the column in the atlas, then x
+/// and y on the field
+type Cell = (usize, usize, u32, u32);
+
+/// Three quarters of the desktop, but not less than the smallest window
+fn windowed_size(video: &VideoSubsystem) -> (u32, u32) {
+    let (min_width, min_height) = MIN_WINDOW_SIZE;
+    match video.desktop_display_mode(0) {
+        Ok(mode) => (
+            max(mode.w.max(0) as u32 * 3 / 4, min_width),
+            max(mode.h.max(0) as u32 * 3 / 4, min_height),
+        ),
+        Err(_) => MIN_WINDOW_SIZE,
+    }
 }
 
-/// This macro does the following:
-/// - defines create_pixmaps() which loads pixmaps
-/// - defines a bench of blah() on SDLWrapper
-macro_rules! load_images {
-    ($($name:ident $count:tt),*) => {
-        fn create_pixmaps(cell_size: &u32) -> HashMap<String, Vec<resvg::tiny_skia::Pixmap>> {
-            let mut pixmaps = HashMap::new();
-            $(load_images_rec!(cell_size, pixmaps, $name, $count);)*
-            pixmaps
+fn show_cursor(show: bool) {
+    unsafe {
+        SDL_ShowCursor(if show { SDL_ENABLE } else { SDL_DISABLE } as i32);
+    }
+}
+
+/// Save the pixels of the screen to `rnake-<date>-<time>.png` in the current directory
+fn save_screenshot(width: u32, height: u32, mut pixels: Vec<u8>) {
+    // the window is opaque, whatever its alpha channel says
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    let Some(pixmap) =
+        IntSize::from_wh(width, height).and_then(|size| Pixmap::from_vec(pixels, size))
+    else {
+        eprintln!("Cannot take a screenshot of an empty screen");
+        return;
+    };
+    let png = match pixmap.encode_png() {
+        Ok(png) => png,
+        Err(e) => {
+            eprintln!("Cannot encode the screenshot: {}", e);
+            return;
         }
-        $(impl<'a> SDLWrapper<'a> {
-            pub fn $name(&mut self, idx: &usize, x: &u32, y: &u32) {
-                assert!(*idx < $count,
-                    "Programming error: there is no image '{}' with index '{}'",
-                    stringify!($name),
-                    $count);
-                let pixmap = &self.pixmaps[stringify!($name)][*idx];
-                let rgba_data = pixmap.data();
-                let width = pixmap.width();
-                let height = pixmap.height();
-                let creator = self.canvas.texture_creator();
-                let mut texture = creator
-                    .create_texture(
-                        Some(PixelFormatEnum::RGBA32),
-                        TextureAccess::Target,
-                        width,
-                        height,
-                    )
-                    .expect("Should be able to create texture");
-                texture
-                    // 4 is one byte for each of RGBA
-                    .update(None, rgba_data, 4 * self.cell as usize)
-                    .expect("Should be able to update texture");
-                let rx = self.border_x + self.cell * *x;
-                let ry = self.border_y + self.cell * *y;
-                let tgt = rect!(rx, ry, self.cell, self.cell);
-                self.canvas
-                    .copy(&texture, None, Some(tgt))
-                    .expect("Should be able to copy texture to canvas");
-            }
-        })*
     };
+    // the screenshots taken in the same second get numbers
+    let stamp = time::timestamp();
+    for n in 0.. {
+        let path = match n {
+            0 => format!("rnake-{}.png", stamp),
+            n => format!("rnake-{}-{}.png", stamp, n),
+        };
+        let saved = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&png));
+        match saved {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => eprintln!("Cannot save the screenshot to {}: {}", path, e),
+            Ok(()) => {}
+        }
+        return;
+    }
 }
 
-const LINE_INTERVAL: u32 = 10;
+/// Copy the images from the atlas to their cells of the field, which starts at the border
+fn copy_cells(
+    canvas: &mut Canvas<Window>,
+    atlas: &Texture,
+    cell: u32,
+    (border_x, border_y): (u32, u32),
+    cells: &[Cell],
+) {
+    for (row, idx, x, y) in cells {
+        let src = rect!(cell * *idx as u32, cell * *row as u32, cell, cell);
+        let tgt = rect!(border_x + cell * x, border_y + cell * y, cell, cell);
+        canvas
+            .copy(atlas, Some(src), Some(tgt))
+            .expect("Should be able to copy texture to canvas");
+    }
+}
+
+/// Free the texture now; with the `unsafe_textures` feature a dropped texture stays until the
+/// canvas which created it is gone
+fn destroy(texture: Texture) {
+    // the canvas lives as long as the wrapper, and nothing uses the texture after this
+    unsafe { texture.destroy() }
+}
 
 pub struct SDLWrapper<'a> {
-    // event pump
-    pub events: EventPump,
+    // event pump; take the events with poll_events()
+    events: EventPump,
+    video: VideoSubsystem,
+    // game controllers are used while they are open
+    controller_subsystem: Option<GameControllerSubsystem>,
+    controllers: Vec<GameController>,
     // graphics
     border_x: u32,
     border_y: u32,
     score_x: u32,
     score_y: u32,
     cell: u32,
+    // the field size given to layout(), to lay it out again when the window changes
+    field_size: (u32, u32),
     canvas: Canvas<Window>,
     // sound player
     pub sounds: Box<dyn Player>,
     // text
     font: Font<'a, 'static>,
-    pixmaps: HashMap<String, Vec<resvg::tiny_skia::Pixmap>>,
+    // all the images at the current cell size, in one texture
+    atlas: Option<Texture>,
+    // the images which do not change while the field is laid out the same way, e.g. the wall
+    // around it; None if they have to be drawn again
+    background: Option<Vec<Cell>>,
+    // the background images are being drawn, so they are remembered instead
+    drawing_background: bool,
+    // the background drawn once; None if the renderer cannot draw to a texture
+    background_layer: Option<Texture>,
+    // the textures are freed with the canvas, or earlier by destroy()
+    creator: TextureCreator<WindowContext>,
+    // take a screenshot when the next frame is presented
+    screenshot: bool,
+    // the screenshots being saved
+    saving: Vec<JoinHandle<()>>,
 }
 
 impl<'a> SDLWrapper<'a> {
-    pub fn new(field_size: &u32, context: &'a Sdl2TtfContext) -> Self {
+    /// Open the window of the given size, or the full screen; call `layout` before drawing
+    /// anything on the field.
+    pub fn new(context: &'a Sdl2TtfContext, window_size: Option<(u32, u32)>) -> Self {
         let sdl_context = sdl2::init().expect("Should be able to get SDL context");
         // Events
         let events = sdl_context
@@ -165,26 +184,29 @@ impl<'a> SDLWrapper<'a> {
         let video_subsystem = sdl_context
             .video()
             .expect("Should be able to get SDL video subsystem");
-        let window = video_subsystem
-            .window("rnake", 0, 0)
-            .fullscreen_desktop()
+        // the full screen game gets a window of its own for when it leaves the full screen
+        let (width, height) = window_size.unwrap_or_else(|| windowed_size(&video_subsystem));
+        let mut builder = video_subsystem.window("rnake", width, height);
+        builder.position_centered().resizable();
+        if window_size.is_none() {
+            builder.fullscreen_desktop();
+        }
+        let mut window = builder
             .build()
             .expect("Should be able to create SDL window");
-        unsafe {
-            SDL_ShowCursor(SDL_DISABLE as i32);
+        let (min_width, min_height) = MIN_WINDOW_SIZE;
+        if let Err(e) = window.set_minimum_size(min_width, min_height) {
+            eprintln!("Cannot limit the window size: {}", e);
         }
-        let window_size = window.size();
+        show_cursor(window_size.is_some());
         let canvas = window
             .into_canvas()
             .build()
             .expect("Should be able to get window's canvas");
-        let square = min(window_size.0, window_size.1);
-        // 2 for the wall around the field
-        let field_plus_wall = *field_size + 2;
-        // we divide and multiply to round the things
-        let cell = square / field_plus_wall;
-        let border_x = (window_size.0 - cell * field_plus_wall) / 2;
-        let border_y = (window_size.1 - cell * field_plus_wall) / 2;
+        let creator = canvas.texture_creator();
+
+        // Game controllers; the ones already plugged in come as ControllerDeviceAdded events
+        let controller_subsystem = sdl_context.game_controller().ok();
 
         // Sounds
         let maybe_audio_subsystem = sdl_context.audio();
@@ -197,81 +219,467 @@ impl<'a> SDLWrapper<'a> {
             .load_font_from_rwops(rwops, 72)
             .expect("Should be able to load font from rwops.");
 
-        let pixmaps = create_pixmaps(&cell);
-
-        load_images!(body 8, fat 3, food 3, headturn 8, headstraight 4, lean 3, mystery 4, obstacle 3, tail 4, wall 1);
-
         Self {
             events,
-            border_x,
-            border_y,
-            score_x: window_size.0 - 150, // TODO
+            video: video_subsystem,
+            controller_subsystem,
+            controllers: vec![],
+            border_x: 0,
+            border_y: 0,
+            score_x: 0,
             score_y: 50,
-            cell,
+            cell: 0,
+            field_size: (0, 0),
             canvas,
             sounds,
             font,
-            pixmaps,
+            atlas: None,
+            background: None,
+            drawing_background: false,
+            background_layer: None,
+            creator,
+            screenshot: false,
+            saving: vec![],
         }
     }
 
+    /// Take all the waiting events. Game controllers are opened and closed here when they are
+    /// plugged in and out, and the field is laid out again when the window size changes, so the
+    /// events should never be taken from the event pump directly.
+    pub fn poll_events(&mut self) -> Vec<Event> {
+        let events: Vec<Event> = self.events.poll_iter().collect();
+        for event in &events {
+            match event {
+                Event::ControllerDeviceAdded { which, .. } => {
+                    let Some(subsystem) = &self.controller_subsystem else {
+                        continue;
+                    };
+                    match subsystem.open(*which) {
+                        Ok(controller) => self.controllers.push(controller),
+                        Err(e) => eprintln!("Cannot open game controller {}: {}", which, e),
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    self.controllers.retain(|c| c.instance_id() != *which);
+                }
+                // the textures drawn to are lost
+                Event::RenderTargetsReset { .. } => self.background = None,
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    let (field_width, field_height) = self.field_size;
+                    if field_width > 0 {
+                        self.layout(field_width, field_height);
+                    }
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+
+    /// The size of the screen area for the field with its wall, in pixels
+    pub fn field_area(&self) -> (u32, u32) {
+        let (win_width, win_height) = self.canvas.window().size();
+        (win_width.saturating_sub(SCORE_WIDTH), win_height)
+    }
+
+    /// Fit the field of the given size into the window, with square cells as large as possible.
+    /// The background has to be drawn again after this.
+    pub fn layout(&mut self, field_width: u32, field_height: u32) {
+        self.field_size = (field_width, field_height);
+        self.background = None;
+        let (area_width, area_height) = self.field_area();
+        // 2 for the wall around the field
+        let (width_plus_wall, height_plus_wall) = (field_width + 2, field_height + 2);
+        // we divide and multiply to round the things
+        // even a window too small for the field gets the images of one pixel
+        let cell = min(area_width / width_plus_wall, area_height / height_plus_wall).max(1);
+        self.border_x = area_width.saturating_sub(cell * width_plus_wall) / 2;
+        self.border_y = area_height.saturating_sub(cell * height_plus_wall) / 2;
+        self.score_x = area_width;
+        if cell != self.cell {
+            self.cell = cell;
+            if let Some(old) = self.atlas.replace(self.create_atlas()) {
+                destroy(old);
+            }
+        }
+    }
+
+    /// Render all the images at the current cell size into one texture: a row for each name in
+    /// SPRITES, with its images from left to right
+    fn create_atlas(&self) -> Texture {
+        let cell = self.cell;
+        let pixmaps = create_pixmaps(&cell);
+        let (width, height) = (cell * ATLAS_COLUMNS, cell * SPRITES.len() as u32);
+        // 4 is one byte for each of RGBA
+        let pitch = 4 * width as usize;
+        let mut rgba_data = vec![0; pitch * height as usize];
+        for (row, (name, _)) in SPRITES.iter().enumerate() {
+            assert!(
+                pixmaps[*name].len() <= ATLAS_COLUMNS as usize,
+                "Programming error: too many '{}' images for the atlas",
+                name
+            );
+            for (column, pixmap) in pixmaps[*name].iter().enumerate() {
+                let image_pitch = 4 * cell as usize;
+                for (line, image_line) in pixmap.data().chunks(image_pitch).enumerate() {
+                    let start = (row * cell as usize + line) * pitch + column * image_pitch;
+                    rgba_data[start..start + image_pitch].copy_from_slice(image_line);
+                }
+            }
+        }
+        let mut atlas = self
+            .creator
+            .create_texture_static(Some(PixelFormatEnum::RGBA32), width, height)
+            .expect("Should be able to create texture");
+        atlas
+            .update(None, &rgba_data, pitch)
+            .expect("Should be able to update texture");
+        atlas.set_blend_mode(BlendMode::Blend);
+        atlas
+    }
+
+    /// Draw the image from the atlas in the cell of the field; while the background is drawn,
+    /// only remember it
+    pub fn sprite(&mut self, name: &str, idx: usize, x: u32, y: u32) {
+        let row = SPRITES
+            .iter()
+            .position(|(sprite, _)| *sprite == name)
+            .expect("Programming error: unknown sprite");
+        assert!(
+            idx < SPRITES[row].1,
+            "Programming error: there is no image '{}' with index '{}'",
+            name,
+            idx
+        );
+        if self.drawing_background {
+            self.background
+                .get_or_insert_with(Vec::new)
+                .push((row, idx, x, y));
+            return;
+        }
+        self.copy_cells(&[(row, idx, x, y)]);
+    }
+
+    fn copy_cells(&mut self, cells: &[Cell]) {
+        let atlas = self
+            .atlas
+            .as_ref()
+            .expect("Programming error: layout() must be called before drawing the field");
+        copy_cells(
+            &mut self.canvas,
+            atlas,
+            self.cell,
+            (self.border_x, self.border_y),
+            cells,
+        );
+    }
+
+    /// Whether the background has to be drawn, between begin_background() and end_background()
+    pub fn needs_background(&self) -> bool {
+        self.background.is_none()
+    }
+
+    /// Start drawing the background: the images drawn until end_background() stay on the field
+    /// until the next layout()
+    pub fn begin_background(&mut self) {
+        self.background = Some(vec![]);
+        self.drawing_background = true;
+    }
+
+    /// Draw the remembered background images to the background layer
+    pub fn end_background(&mut self) {
+        self.drawing_background = false;
+        if let Some(old) = self.background_layer.take() {
+            destroy(old);
+        }
+        let (win_width, win_height) = self.canvas.window().size();
+        let Ok(mut layer) = self
+            .creator
+            .create_texture_target(None, win_width, win_height)
+        else {
+            // the images are drawn one by one on every frame then
+            return;
+        };
+        let atlas = self
+            .atlas
+            .as_ref()
+            .expect("Programming error: layout() must be called before drawing the field");
+        let cells = self.background.as_deref().unwrap_or_default();
+        let (cell, border) = (self.cell, (self.border_x, self.border_y));
+        let drawn = self.canvas.with_texture_canvas(&mut layer, |canvas| {
+            canvas.set_draw_color(Color::BLACK);
+            canvas.clear();
+            copy_cells(canvas, atlas, cell, border, cells);
+        });
+        match drawn {
+            Ok(()) => self.background_layer = Some(layer),
+            Err(_) => destroy(layer),
+        }
+    }
+
+    /// Draw the background over the whole window
+    pub fn draw_background(&mut self) {
+        match &self.background_layer {
+            Some(layer) => self
+                .canvas
+                .copy(layer, None, None)
+                .expect("Should be able to copy texture to canvas"),
+            None => {
+                let cells = self.background.take().unwrap_or_default();
+                self.clear();
+                self.copy_cells(&cells);
+                self.background = Some(cells);
+            }
+        }
+    }
+
+    /// Switch between the full screen and the window; the field is laid out again when SDL tells
+    /// about the new window size
+    pub fn toggle_fullscreen(&mut self) {
+        let window = self.canvas.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        if let Err(e) = window.set_fullscreen(fullscreen) {
+            eprintln!("Cannot switch the full screen mode: {}", e);
+            return;
+        }
+        show_cursor(fullscreen == FullscreenType::Off);
+    }
+
     pub fn clear(&mut self) {
         self.canvas.set_draw_color(Color::BLACK);
         self.canvas.clear();
     }
     pub fn present(&mut self) {
+        if self.screenshot {
+            self.screenshot = false;
+            self.take_screenshot();
+        }
         self.canvas.present();
     }
+    /// Save what is on the screen to a PNG file, when the next frame is presented
+    pub fn screenshot(&mut self) {
+        self.screenshot = true;
+    }
+    /// Read the frame about to be presented, and save it on another thread so that the game does
+    /// not wait for the file
+    fn take_screenshot(&mut self) {
+        let read = self.canvas.output_size().and_then(|(width, height)| {
+            let pixels = self.canvas.read_pixels(None, PixelFormatEnum::RGBA32)?;
+            Ok((width, height, pixels))
+        });
+        let (width, height, pixels) = match read {
+            Ok(read) => read,
+            Err(e) => {
+                eprintln!("Cannot take a screenshot: {}", e);
+                return;
+            }
+        };
+        self.saving.retain(|saving| !saving.is_finished());
+        self.saving.push(thread::spawn(move || {
+            save_screenshot(width, height, pixels)
+        }));
+    }
     pub fn window(&self) -> Option<&Window> {
         Some(self.canvas.window())
     }
     pub fn messages(&mut self, messages: Vec<&str>) {
+        self.text_lines(&messages, None);
+    }
+    /// Show the menu items, the selected one highlighted
+    pub fn menu(&mut self, items: &[String], selected: usize) {
+        let items: Vec<&str> = items.iter().map(|item| item.as_ref()).collect();
+        self.text_lines(&items, Some(selected));
+    }
+    fn text_lines(&mut self, messages: &[&str], highlight: Option<usize>) {
         self.clear();
-        let surfaces = messages.iter().map(|line| {
-            self.font
-                .render(line)
-                .solid(Color::BLUE)
-                .expect("Should be able to render text line")
-        });
+        self.draw_lines(messages, highlight);
+        self.present();
+    }
+    /// Show the messages over what is on the screen, which stays visible as it is
+    pub fn caption(&mut self, messages: &[&str]) {
+        self.draw_lines(messages, None);
+        self.present();
+    }
+    /// Darken what is on the screen and show the messages over it
+    pub fn overlay(&mut self, messages: &[&str]) {
+        let (win_width, win_height) = self.canvas.window().size();
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas
+            .set_draw_color(Color::RGBA(0, 0, 0, OVERLAY_ALPHA));
+        self.canvas
+            .fill_rect(rect!(0, 0, win_width, win_height))
+            .expect("Should be able to draw the overlay");
+        self.canvas.set_blend_mode(BlendMode::None);
+        self.draw_lines(messages, None);
+        self.present();
+    }
+    fn draw_lines(&mut self, messages: &[&str], highlight: Option<usize>) {
+        // SDL_ttf cannot render an empty line
+        let surfaces = messages
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(idx, line)| {
+                let color = if highlight == Some(idx) {
+                    Color::YELLOW
+                } else {
+                    Color::BLUE
+                };
+                self.font
+                    .render(line)
+                    .solid(color)
+                    .expect("Should be able to render text line")
+            });
         let creator = self.canvas.texture_creator();
-        let textures = surfaces.map(|surface| {
-            creator
-                .create_texture_from_surface(surface)
-                .expect("Should be able to create texture from surface")
-        });
-        let heights: u32 = textures.clone().map(|texture| texture.query().height).sum();
-        let total_height = heights + (messages.len() as u32 - 1) * LINE_INTERVAL;
+        let textures: Vec<_> = surfaces
+            .map(|surface| {
+                creator
+                    .create_texture_from_surface(surface)
+                    .expect("Should be able to create texture from surface")
+            })
+            .collect();
+        if textures.is_empty() {
+            return;
+        }
+        let heights: u32 = textures.iter().map(|texture| texture.query().height).sum();
+        let total_height = heights + (textures.len() as u32 - 1) * LINE_INTERVAL;
+        let max_width = textures
+            .iter()
+            .map(|texture| texture.query().width)
+            .max()
+            .unwrap_or(0);
         let (win_width, win_height) = self
             .window()
             .expect("Should be able to get window corresponding to the texture")
             .size();
-        let mut pad_h = (win_height - total_height) / 2;
+        // shrink the text which does not fit the window
+        let scale = f64::min(
+            1.0,
+            f64::min(
+                win_height as f64 / total_height as f64,
+                win_width as f64 / max_width as f64,
+            ),
+        );
+        let mut pad_h = (win_height as f64 - total_height as f64 * scale) / 2.0;
 
         for texture in textures {
             let TextureQuery { width, height, .. } = texture.query();
-            let tgt = rect!((win_width - width) / 2, pad_h, width, height);
+            let (width, height) = (width as f64 * scale, height as f64 * scale);
+            let tgt = rect!((win_width as f64 - width) / 2.0, pad_h, width, height);
             self.canvas
                 .copy(&texture, None, Some(tgt))
                 .expect("Should be able to copy texture to canvas.");
+            destroy(texture);
             pad_h += height;
-            pad_h += LINE_INTERVAL;
+            pad_h += LINE_INTERVAL as f64 * scale;
         }
-        self.present();
     }
-    pub fn score(&mut self, sc: u32) {
-        let surface = self
-            .font
-            .render(sc.to_string().as_ref())
-            .solid(Color::YELLOW)
-            .expect("Should be able to render score");
+    /// Let the player type in a line of text under the prompt, until RETURN (or A or Start on a
+    /// game controller) or ESC (or B or Back) is pressed or the window is closed.
+    pub fn text_input(&mut self, prompt: &str) -> Typed {
+        let text_input = self.video.text_input();
+        text_input.start();
+        // forget the keys pressed before, e.g. the one which brought us here
+        self.poll_events();
+        let mut text = String::new();
+        let result = 'typing: loop {
+            self.messages(vec![prompt, format!("{}_", text).as_ref()]);
+            for event in self.poll_events() {
+                match event {
+                    Event::TextInput { text: typed, .. } => {
+                        text.extend(typed.chars().take(MAX_INPUT - text.chars().count()));
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Backspace),
+                        ..
+                    } => {
+                        text.pop();
+                    }
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Return | Keycode::KpEnter),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::A | Button::Start,
+                        ..
+                    } => break 'typing Typed::Text(text),
+                    Event::Quit { .. } => break 'typing Typed::Closed,
+                    Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    }
+                    | Event::ControllerButtonDown {
+                        button: Button::B | Button::Back,
+                        ..
+                    } => break 'typing Typed::Cancelled,
+                    _ => {}
+                }
+            }
+            unsafe {
+                SDL_Delay(20);
+            }
+        };
+        text_input.stop();
+        result
+    }
+    /// Show the prompt and wait for a key; None if the window is closed
+    pub fn read_key(&mut self, prompt: &str) -> Option<Keycode> {
+        self.messages(vec![prompt]);
+        // forget the keys pressed before, e.g. the one which brought us here
+        self.poll_events();
+        loop {
+            for event in self.poll_events() {
+                match event {
+                    Event::KeyDown {
+                        keycode: Some(keycode),
+                        ..
+                    } => return Some(keycode),
+                    Event::Quit { .. } => return None,
+                    _ => {}
+                }
+            }
+            unsafe {
+                SDL_Delay(20);
+            }
+        }
+    }
+    /// Show the scores next to the field, a line for every player
+    pub fn scores(&mut self, scores: &[u32]) {
         let creator = self.canvas.texture_creator();
-        let texture = creator
-            .create_texture_from_surface(surface)
-            .expect("Should be able to create texture from surface");
-        let TextureQuery { width, height, .. } = texture.query();
-        let tgt = rect!(self.score_x, self.score_y, width, height);
-        self.canvas
-            .copy(&texture, None, Some(tgt))
-            .expect("Should be able to copy texture to canvas");
+        let mut y = self.score_y;
+        for sc in scores {
+            let surface = self
+                .font
+                .render(sc.to_string().as_ref())
+                .solid(Color::YELLOW)
+                .expect("Should be able to render score");
+            let texture = creator
+                .create_texture_from_surface(surface)
+                .expect("Should be able to create texture from surface");
+            let TextureQuery { width, height, .. } = texture.query();
+            let tgt = rect!(self.score_x, y, width, height);
+            self.canvas
+                .copy(&texture, None, Some(tgt))
+                .expect("Should be able to copy texture to canvas");
+            destroy(texture);
+            y += height + LINE_INTERVAL;
+        }
+    }
+}
+
+impl Drop for SDLWrapper<'_> {
+    /// Do not lose the screenshots taken just before the game is over
+    fn drop(&mut self) {
+        for saving in self.saving.drain(..) {
+            if saving.join().is_err() {
+                eprintln!("Cannot save a screenshot");
+            }
+        }
     }
 }
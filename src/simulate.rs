@@ -0,0 +1,218 @@
+//! Many games played quickly by a computer player, for the statistics of the game: the scores,
+//! how long the games are, how they end and how often the things appear. It needs no frontend,
+//! build it with `cargo build --bin rnake-sim --no-default-features`.
+//!
+//! The CSV output has a line per game; the JSON one has the summary of all the games too.
+
+use std::process;
+use std::thread;
+
+use rnake::autopilot::{self, STRATEGIES};
+use rnake::batch::{self, GameStats, Summary, DEATHS};
+use rnake::cli::{self, ConfigOptions};
+use rnake::config::GameConfig;
+use rnake::level::{self, Level};
+use rnake::world::Spawned;
+
+const USAGE: &str = "Usage: rnake-sim [--config <file>] [--set <key>=<value>]... \
+                     [--strategy greedy|bfs|hamiltonian] [--games <number>] [--seed <number>] \
+                     [--ticks <number>] [--format csv|json] [--campaign]";
+
+// the defaults of the options
+const GAMES: u64 = 1000;
+const TICKS: u64 = 10000;
+const STRATEGY: &str = "bfs";
+
+enum Format {
+    Csv,
+    Json,
+}
+
+/// Command line options
+struct Options {
+    game: ConfigOptions,
+    // one of autopilot::STRATEGIES
+    strategy: String,
+    games: u64,
+    // the seed of the first game; every next game has the next seed
+    seed: u64,
+    // stop a game after this many steps
+    ticks: u64,
+    format: Format,
+    // play the levels of the campaign instead of only the open field of the configuration
+    campaign: bool,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String> + 'static) -> Result<Self, String> {
+        let mut options = Options {
+            game: ConfigOptions::default(),
+            strategy: STRATEGY.to_string(),
+            games: GAMES,
+            seed: 0,
+            ticks: TICKS,
+            format: Format::Csv,
+            campaign: false,
+        };
+        cli::parse(args, |name, args| {
+            match name {
+                "--strategy" => {
+                    let v = args.value()?;
+                    if !STRATEGIES.contains(&v.as_ref()) {
+                        return Err(format!("Unknown strategy '{}'", v));
+                    }
+                    options.strategy = v;
+                }
+                "--games" => options.games = args.parsed("number of games")?,
+                "--seed" => options.seed = args.parsed("seed")?,
+                "--ticks" => options.ticks = args.parsed("number of ticks")?,
+                "--campaign" => options.campaign = true,
+                "--format" => {
+                    options.format = match args.value()?.as_ref() {
+                        "csv" => Format::Csv,
+                        "json" => Format::Json,
+                        v => return Err(format!("Unknown format '{}'", v)),
+                    };
+                }
+                _ => return options.game.option(name, args),
+            }
+            Ok(true)
+        })?;
+        Ok(options)
+    }
+}
+
+pub fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("{}\n{}", msg, USAGE);
+        process::exit(2);
+    });
+    let config = options.game.load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+    let levels = if options.campaign {
+        level::campaign(&config)
+    } else {
+        vec![Level::open(config.width(), config.field_height, None)]
+    };
+    let games = play_all(&config, &levels, &options);
+    match options.format {
+        Format::Csv => print_csv(&games),
+        Format::Json => print_json(&games),
+    }
+}
+
+/// Play the games on all the processors; the games are in the order of their seeds
+fn play_all(config: &GameConfig, levels: &[Level], options: &Options) -> Vec<GameStats> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get() as u64);
+    let mut games: Vec<GameStats> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|first| {
+                scope.spawn(move || {
+                    // every thread has its own player, which may remember things between steps
+                    let mut controller = autopilot::strategy(&options.strategy)
+                        .expect("Programming error: the strategy must be checked");
+                    (first..options.games)
+                        .step_by(threads as usize)
+                        .map(|n| {
+                            let seed = options.seed.wrapping_add(n);
+                            batch::play(config, levels, controller.as_mut(), seed, options.ticks)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("Should be able to play the games"))
+            .collect()
+    });
+    games.sort_by_key(|g| g.seed.wrapping_sub(options.seed));
+    games
+}
+
+fn print_csv(games: &[GameStats]) {
+    println!("seed,score,ticks,length,level,death,food,lean,fat,obstacle,mystery");
+    for g in games {
+        let s = &g.spawned;
+        println!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            g.seed,
+            g.score,
+            g.ticks,
+            g.length,
+            g.level + 1,
+            batch::death_name(g.death),
+            s.food,
+            s.lean,
+            s.fat,
+            s.obstacle,
+            s.mystery
+        );
+    }
+}
+
+fn print_json(games: &[GameStats]) {
+    let summary = Summary::new(games);
+    let distribution = |d: &batch::Distribution| {
+        format!(
+            r#"{{"min": {}, "q1": {}, "median": {}, "q3": {}, "max": {}, "mean": {:.2}}}"#,
+            d.min, d.q1, d.median, d.q3, d.max, d.mean
+        )
+    };
+    let deaths: Vec<String> = DEATHS
+        .iter()
+        .zip(summary.deaths)
+        .map(|(name, count)| format!(r#""{}": {}"#, name, count))
+        .collect();
+    // how often the things appear, per 1000 steps of all the games
+    let ticks: u64 = games.iter().map(|g| g.ticks).sum();
+    let per_1000_ticks = |n: u32| n as f64 * 1000.0 / ticks.max(1) as f64;
+    let Spawned {
+        food,
+        lean,
+        fat,
+        obstacle,
+        mystery,
+    } = summary.spawned;
+
+    println!("{{");
+    println!(r#"  "games": {},"#, summary.games);
+    println!(r#"  "score": {},"#, distribution(&summary.score));
+    println!(r#"  "ticks": {},"#, distribution(&summary.ticks));
+    println!(r#"  "deaths": {{{}}},"#, deaths.join(", "));
+    println!(
+        r#"  "spawned": {{"food": {}, "lean": {}, "fat": {}, "obstacle": {}, "mystery": {}}},"#,
+        food, lean, fat, obstacle, mystery
+    );
+    println!(
+        r#"  "spawned_per_1000_ticks": {{"food": {:.2}, "lean": {:.2}, "fat": {:.2}, "obstacle": {:.2}, "mystery": {:.2}}},"#,
+        per_1000_ticks(food),
+        per_1000_ticks(lean),
+        per_1000_ticks(fat),
+        per_1000_ticks(obstacle),
+        per_1000_ticks(mystery)
+    );
+    println!(r#"  "results": ["#);
+    for (idx, g) in games.iter().enumerate() {
+        let s = &g.spawned;
+        println!(
+            r#"    {{"seed": {}, "score": {}, "ticks": {}, "length": {}, "level": {}, "death": "{}", "spawned": {{"food": {}, "lean": {}, "fat": {}, "obstacle": {}, "mystery": {}}}}}{}"#,
+            g.seed,
+            g.score,
+            g.ticks,
+            g.length,
+            g.level + 1,
+            batch::death_name(g.death),
+            s.food,
+            s.lean,
+            s.fat,
+            s.obstacle,
+            s.mystery,
+            if idx + 1 < games.len() { "," } else { "" }
+        );
+    }
+    println!("  ]");
+    println!("}}");
+}
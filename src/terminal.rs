@@ -0,0 +1,263 @@
+use std::io::{self, BufWriter, Stdout, Write};
+use std::time::Duration;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, DisableFocusChange, EnableFocusChange, Event};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use rnake::render::Renderer;
+use rnake::world::{Direction, SnakeCell, Thing, World};
+
+// what one cell of the field looks like: two columns, so that the cells are about square
+type Glyph = (&'static str, Color);
+
+const EMPTY: Glyph = ("  ", Color::Reset);
+const BORDER: Glyph = ("██", Color::DarkGrey);
+const PORTAL: Glyph = ("░░", Color::Blue);
+const WALL: Glyph = ("▓▓", Color::Grey);
+// the colors of the snake and of its tail, for every player
+const SNAKE_COLORS: [(Color, Color); 2] = [
+    (Color::Green, Color::DarkGreen),
+    (Color::Cyan, Color::DarkCyan),
+];
+
+/// The terminal in raw mode, on the alternate screen; dropping it gives the terminal back to the
+/// shell as it was.
+pub struct Terminal {
+    out: BufWriter<Stdout>,
+    cols: u16,
+    rows: u16,
+}
+
+impl Terminal {
+    /// The room for the field with its border, in cells: a cell takes two columns, and the score
+    /// takes the last row
+    pub fn field_area() -> io::Result<(u32, u32)> {
+        let (cols, rows) = terminal::size()?;
+        Ok((u32::from(cols) / 2, u32::from(rows).saturating_sub(1)))
+    }
+
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = BufWriter::new(io::stdout());
+        execute!(
+            out,
+            EnterAlternateScreen,
+            Hide,
+            EnableFocusChange,
+            Clear(ClearType::All)
+        )?;
+        let (cols, rows) = terminal::size()?;
+        Ok(Terminal { out, cols, rows })
+    }
+
+    /// Wait up to the timeout for the first event, then take all the events which are there.
+    /// When the terminal changes its size, it is cleared for the next frame.
+    pub fn poll_events(&mut self, timeout: Duration) -> Vec<Event> {
+        let mut events = vec![];
+        let mut timeout = timeout;
+        while event::poll(timeout).expect("Should be able to poll terminal events") {
+            let event = event::read().expect("Should be able to read terminal events");
+            if let Event::Resize(cols, rows) = event {
+                self.cols = cols;
+                self.rows = rows;
+                self.clear();
+            }
+            events.push(event);
+            timeout = Duration::ZERO;
+        }
+        events
+    }
+
+    /// Whether the field of the world with its border, and the score below it, fit the terminal
+    pub fn fits(&self, w: &World) -> bool {
+        (w.width() + 2) * 2 <= u32::from(self.cols) && w.height() + 3 <= u32::from(self.rows)
+    }
+
+    pub fn clear(&mut self) {
+        queue!(self.out, ResetColor, Clear(ClearType::All))
+            .expect("Should be able to clear the terminal");
+    }
+
+    pub fn present(&mut self) {
+        self.out
+            .flush()
+            .expect("Should be able to write to the terminal");
+    }
+
+    /// Show the messages on the empty screen, one per line, in the middle
+    pub fn messages(&mut self, messages: &[&str]) {
+        self.clear();
+        self.draw_lines(messages, false);
+        self.present();
+    }
+
+    /// Show the messages over what is on the screen
+    pub fn overlay(&mut self, messages: &[&str]) {
+        self.draw_lines(messages, true);
+        self.present();
+    }
+
+    fn draw_lines(&mut self, messages: &[&str], boxed: bool) {
+        let width = messages
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let top = (self.rows as usize).saturating_sub(messages.len()) / 2;
+        if boxed {
+            queue!(self.out, SetAttribute(Attribute::Reverse))
+                .expect("Should be able to write to the terminal");
+        }
+        for (idx, line) in messages.iter().enumerate() {
+            // the box is as wide as the longest line, with a space on each side
+            let line = if boxed {
+                format!(" {:^width$} ", line)
+            } else {
+                line.to_string()
+            };
+            let left = (self.cols as usize).saturating_sub(line.chars().count()) / 2;
+            queue!(
+                self.out,
+                MoveTo(left as u16, (top + idx) as u16),
+                SetForegroundColor(Color::Blue),
+                Print(line)
+            )
+            .expect("Should be able to write to the terminal");
+        }
+        queue!(self.out, SetAttribute(Attribute::Reset), ResetColor)
+            .expect("Should be able to write to the terminal");
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // nothing else can be done if the terminal is gone
+        let _ = execute!(
+            self.out,
+            ResetColor,
+            DisableFocusChange,
+            Show,
+            LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl Renderer for Terminal {
+    /// Draw the frame in the middle of the terminal; the caller presents it
+    fn draw(&mut self, w: &World) {
+        // the field with its border
+        let (width, height) = (w.width() as usize + 2, w.height() as usize + 2);
+        if !self.fits(w) {
+            queue!(
+                self.out,
+                Clear(ClearType::All),
+                MoveTo(0, 0),
+                Print("The terminal is too small, make it bigger")
+            )
+            .expect("Should be able to write to the terminal");
+            return;
+        }
+
+        let mut cells = vec![EMPTY; width * height];
+        let mut put = |x: u32, y: u32, glyph: Glyph| cells[y as usize * width + x as usize] = glyph;
+
+        // without the wall the snake goes through portals to the other side of the field
+        let border = if w.config().wrap { PORTAL } else { BORDER };
+        for x in 0..width as u32 {
+            put(x, 0, border);
+            put(x, height as u32 - 1, border);
+        }
+        for y in 0..height as u32 {
+            put(0, y, border);
+            put(width as u32 - 1, y, border);
+        }
+        for t in &w.things {
+            put(t.x, t.y, thing(&t.what));
+        }
+
+        for (player, snake) in w.snakes.iter().enumerate() {
+            let (color, tail_color) = SNAKE_COLORS[player % SNAKE_COLORS.len()];
+            let l = snake.cells.len() - 1;
+            for (idx, s) in snake.cells.iter().enumerate() {
+                let glyph = match idx {
+                    0 => head(&s.dir, color),
+                    _ if idx == l => ("▒▒", tail_color),
+                    _ => body(s, color),
+                };
+                put(s.coords.x, s.coords.y, glyph);
+            }
+        }
+
+        let left = ((self.cols as usize - width * 2) / 2) as u16;
+        let top = ((self.rows as usize - height - 1) / 2) as u16;
+        let mut color = None;
+        for (y, row) in cells.chunks(width).enumerate() {
+            queue!(self.out, MoveTo(left, top + y as u16))
+                .expect("Should be able to write to the terminal");
+            for (text, fg) in row {
+                // change the color only when it changes, which is rare
+                if color != Some(*fg) {
+                    queue!(self.out, SetForegroundColor(*fg))
+                        .expect("Should be able to write to the terminal");
+                    color = Some(*fg);
+                }
+                queue!(self.out, Print(text)).expect("Should be able to write to the terminal");
+            }
+        }
+        queue!(
+            self.out,
+            ResetColor,
+            MoveTo(left, top + height as u16),
+            Print(score(w))
+        )
+        .expect("Should be able to write to the terminal");
+    }
+}
+
+/// The score, or the score of every player
+fn score(w: &World) -> String {
+    if w.snakes.len() == 1 {
+        return format!("Score: {}", w.score());
+    }
+    let scores: Vec<String> = w
+        .snakes
+        .iter()
+        .enumerate()
+        .map(|(player, s)| format!("Player {}: {}", player + 1, s.score))
+        .collect();
+    scores.join("  ")
+}
+
+fn head(dir: &Direction, color: Color) -> Glyph {
+    let text = match dir {
+        Direction::Up => "▴▴",
+        Direction::Down => "▾▾",
+        Direction::Left => "◂◂",
+        Direction::Right => "▸▸",
+    };
+    (text, color)
+}
+
+// the snake is striped like in the pictures
+fn body(s: &SnakeCell, color: Color) -> Glyph {
+    if s.even {
+        ("██", color)
+    } else {
+        ("▓▓", color)
+    }
+}
+
+fn thing(what: &Thing) -> Glyph {
+    match what {
+        Thing::Food => ("()", Color::Red),
+        Thing::Fat => ("{}", Color::Magenta),
+        Thing::Lean => ("[]", Color::Yellow),
+        Thing::Mystery => ("??", Color::Cyan),
+        Thing::Obstacle => ("XX", Color::DarkRed),
+        Thing::Wall => WALL,
+    }
+}
@@ -0,0 +1,65 @@
+//! The date and the time, in UTC, without a calendar library.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Today's date as YYYY-MM-DD, in UTC
+pub fn today() -> String {
+    let (year, month, day) = civil_date(now() / 86400);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The current date and time as YYYY-MM-DD-HHMMSS, in UTC, e.g. for file names
+pub fn timestamp() -> String {
+    let secs = now();
+    let (year, month, day) = civil_date(secs / 86400);
+    let time = secs % 86400;
+    format!(
+        "{:04}-{:02}-{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+// seconds since 1970-01-01
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// The year, month and day of the given day since 1970-01-01; see
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // count from 0000-03-01, so that the leap day is the last day of the year
+    let days = days + 719468;
+    // 400 years are exactly 146097 days
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months from March, each 5 months are 153 days
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11016), (2000, 2, 29));
+        // 2100 is not a leap year
+        assert_eq!(civil_date(47540), (2100, 2, 28));
+        assert_eq!(civil_date(47541), (2100, 3, 1));
+    }
+}
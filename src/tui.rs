@@ -0,0 +1,247 @@
+//! Rnake in a terminal, e.g. over SSH: the same game as the SDL2 one, drawn with Unicode and ANSI
+//! colors. It needs neither SDL2 nor vcpkg, build it with
+//! `cargo build --bin rnake-tui --no-default-features --features tui`.
+
+mod options;
+mod terminal;
+
+use std::process;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use options::Options;
+use rnake::config::{GameConfig, MAX_FIELD_SIZE, MIN_FIELD_SIZE};
+use rnake::game::{Game, Pause, PauseChange, Screen};
+use rnake::input::Steer;
+use rnake::level;
+use rnake::render::Renderer;
+use rnake::replay::Replay;
+use rnake::world::Direction;
+use terminal::Terminal;
+
+const USAGE: &str = "Usage: rnake-tui [--config <file>] [--set <key>=<value>]... \
+                     [--seed <number>] [--replay <file>]";
+
+// read the keyboard at least this often
+const WAIT: Duration = Duration::from_millis(20);
+
+/// What the player wants. The keys are fixed: the arrows point the snake, P pauses the game,
+/// ESC (or Q, or Ctrl-C) quits it, SPACE (or RETURN) starts a new one.
+enum Input {
+    Point(Direction),
+    Pause,
+    Quit,
+    Confirm,
+    // the terminal has changed its size, or the player came back to it
+    Redraw,
+    FocusLost,
+}
+
+pub fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("{}\n{}", msg, USAGE);
+        process::exit(2);
+    });
+    if options.window.is_some() {
+        eprintln!("The terminal game has no window\n{}", USAGE);
+        process::exit(2);
+    }
+    let playback = options.replay.as_deref().map(|path| {
+        Replay::load(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path.display(), e);
+            process::exit(2);
+        })
+    });
+    let mut config = match &playback {
+        Some(replay) => replay.config.clone(),
+        None => options.game.load().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(2);
+        }),
+    };
+
+    let area = Terminal::field_area().unwrap_or_else(|e| {
+        eprintln!("Cannot use the terminal: {}", e);
+        process::exit(1);
+    });
+    fit_field(&mut config, playback.is_some(), area);
+    let levels = level::campaign(&config);
+    let first_level = playback.as_ref().map_or(0, |replay| replay.level);
+    if first_level >= levels.len() {
+        eprintln!("There is no level {}", first_level);
+        process::exit(2);
+    }
+
+    let mut term = Terminal::new().unwrap_or_else(|e| {
+        eprintln!("Cannot use the terminal: {}", e);
+        process::exit(1);
+    });
+
+    'game: loop {
+        let mut game = match &playback {
+            Some(replay) => Game::replay(&config, &levels, replay),
+            None => Game::new(
+                &config,
+                &levels,
+                first_level,
+                options.seed.unwrap_or_else(rand::random),
+            ),
+        };
+        run(&mut term, &mut game);
+
+        let mut lines = game.summary();
+        // the terminal is busy with the game, so the error is shown instead of printed
+        if let Some(Err(e)) = game.recording().map(|replay| replay.save_here()) {
+            lines.push(e.to_string());
+        }
+        lines.push("Press SPACE to play again,".to_string());
+        lines.push("ESC to exit.".to_string());
+        let lines: Vec<&str> = lines.iter().map(|line| line.as_ref()).collect();
+        term.messages(&lines);
+        loop {
+            for event in term.poll_events(WAIT) {
+                match input(event) {
+                    Some(Input::Confirm) => continue 'game,
+                    Some(Input::Quit) => break 'game,
+                    Some(Input::Redraw) => term.messages(&lines),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Play the game until it is over or the player quits it
+fn run(term: &mut Terminal, game: &mut Game) {
+    let mut next_frame = Instant::now();
+    let mut pause = Pause::default();
+    term.clear();
+
+    'running: loop {
+        let timeout = if pause.paused() {
+            WAIT
+        } else {
+            next_frame
+                .saturating_duration_since(Instant::now())
+                .min(WAIT)
+        };
+        for event in term.poll_events(timeout) {
+            match input(event) {
+                Some(Input::Point(dir)) => {
+                    if !pause.paused() {
+                        game.steer(0, Steer::Direction(dir));
+                    }
+                }
+                Some(Input::Pause) => pause.key(),
+                Some(Input::Quit) => {
+                    game.quit();
+                    return;
+                }
+                Some(Input::Redraw) => pause.exposed(),
+                Some(Input::FocusLost) => pause.focus_lost(),
+                Some(Input::Confirm) | None => {}
+            }
+        }
+
+        // the game also pauses while the terminal is too small for the field, and does not go
+        // on until it is large enough again
+        let fits = term.fits(game.world());
+        match pause.update(fits) {
+            PauseChange::Resume => {
+                if !game.resume(term) {
+                    return;
+                }
+            }
+            PauseChange::Show => {
+                term.draw(game.world());
+                if fits {
+                    term.overlay(&["Paused", "Press P to resume"]);
+                } else {
+                    term.present();
+                }
+            }
+            PauseChange::Nothing => {}
+        }
+
+        if pause.paused() || Instant::now() < next_frame {
+            continue 'running;
+        }
+
+        let step = game.step();
+        if game.over() {
+            return;
+        }
+        if step.next_level {
+            if !game.show_level(term) {
+                return;
+            }
+            term.clear();
+        }
+
+        term.draw(game.world());
+        term.present();
+
+        next_frame = Instant::now() + Duration::from_millis(game.world().config().frame_delta);
+    }
+}
+
+impl Screen for Terminal {
+    fn overlay(&mut self, lines: &[&str]) {
+        Terminal::overlay(self, lines);
+    }
+
+    fn messages(&mut self, lines: &[&str]) {
+        Terminal::messages(self, lines);
+    }
+
+    fn skip_input(&mut self) -> bool {
+        self.poll_events(Duration::ZERO)
+            .into_iter()
+            .any(|event| matches!(input(event), Some(Input::Quit)))
+    }
+}
+
+/// What the terminal event means for the game, if anything
+fn input(event: Event) -> Option<Input> {
+    match event {
+        Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press | KeyEventKind::Repeat,
+            ..
+        }) => match code {
+            KeyCode::Up => Some(Input::Point(Direction::Up)),
+            KeyCode::Down => Some(Input::Point(Direction::Down)),
+            KeyCode::Left => Some(Input::Point(Direction::Left)),
+            KeyCode::Right => Some(Input::Point(Direction::Right)),
+            // in the raw mode Ctrl-C is just a key
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Some(Input::Quit),
+            KeyCode::Char('p' | 'P') => Some(Input::Pause),
+            KeyCode::Esc | KeyCode::Char('q' | 'Q') => Some(Input::Quit),
+            KeyCode::Char(' ') | KeyCode::Enter => Some(Input::Confirm),
+            _ => None,
+        },
+        Event::Resize(..) | Event::FocusGained => Some(Input::Redraw),
+        Event::FocusLost => Some(Input::FocusLost),
+        _ => None,
+    }
+}
+
+/// Make the field fit the terminal, whose cells are one row high and two columns wide. Without
+/// the width set the field is as wide as the terminal, and a new game gets a lower field if the
+/// terminal is not tall enough; a replay must be played on its own field.
+fn fit_field(config: &mut GameConfig, replay: bool, area: (u32, u32)) {
+    let (width, height) = area;
+    // the border takes one cell on each side
+    if !replay && config.field_height + 2 > height {
+        config.field_height = height.saturating_sub(2).max(MIN_FIELD_SIZE);
+    }
+    if config.field_width.is_none() {
+        config.field_width = Some(
+            width
+                .saturating_sub(2)
+                .clamp(MIN_FIELD_SIZE, MAX_FIELD_SIZE),
+        );
+    }
+}
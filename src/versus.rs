@@ -0,0 +1,37 @@
+//! Two players on one keyboard: the first one steers with the keys from the bindings (or a game
+//! controller), the second one turns with A and D, or with the absolute steering points the snake
+//! with W, A, S and D. The game is over when a snake dies; the other one wins, and if both die at
+//! once, the higher score wins.
+
+use rnake::config::GameConfig;
+use rnake::game::Game;
+use rnake::level::Level;
+
+use crate::bindings::Bindings;
+use crate::sdlwrapper::SDLWrapper;
+use crate::{game_over, run, AfterGame};
+
+/// Play versus games on the arena as large as the field until a player quits. The games are
+/// neither saved as replays nor put in the high scores. Returns true if the window was closed.
+pub fn play(sdl: &mut SDLWrapper, config: &GameConfig, bindings: &Bindings) -> bool {
+    sdl.sounds.start();
+    let arena = [Level::arena(config.width(), config.field_height)];
+    loop {
+        let mut game = Game::new(config, &arena, 0, rand::random());
+        if run(sdl, bindings, &mut game) {
+            return true;
+        }
+
+        let mut lines = game.summary();
+        lines.push(format!("Press {} to play again,", bindings.confirm.name()));
+        lines.push(format!(
+            "{} to return to the title screen.",
+            bindings.quit.name()
+        ));
+        match game_over(sdl, bindings, &lines) {
+            AfterGame::Again => {}
+            AfterGame::Leave => return false,
+            AfterGame::Closed => return true,
+        }
+    }
+}
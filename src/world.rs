@@ -1,31 +1,52 @@
-use rand::{distributions::Uniform, Rng};
-
-pub const FIELD_SIZE: u32 = 30;
-pub const FOOD_LIFETIME: u32 = 60;
-pub const OBSTACLE_LIFETIME: u32 = 60;
-pub const OBSTACLE_P: f32 = 0.015;
-pub const MYSTERY_P: f32 = 0.0025;
-pub const MYSTERY_LIFETIME: u32 = 120;
-pub const MYSTERY_SCORE: u32 = 5;
-pub const MYSTERY_GROW_SNAKE: u32 = 15;
-pub const LEAN_P: f32 = 0.5;
-pub const LEAN_AFTER_FOOD: u32 = 5;
-pub const LEAN_LIFETIME: u32 = 60;
-pub const FAT_P: f32 = 0.1;
-pub const FAT_GROW_SNAKE: u32 = 6;
+use std::cmp::min;
+use std::fmt;
 
+use rand::{distributions::Uniform, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::config::GameConfig;
+use crate::level::Level;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum StepError {
     Obstacle,
     OutOfField,
     SelfHit,
+    Wall,
+    // the head hit another snake
+    Snake,
+    // two heads met in the same cell
+    HeadOn,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self {
+            StepError::Obstacle => "hit an obstacle",
+            StepError::OutOfField => "hit the wall",
+            StepError::SelfHit => "hit itself",
+            StepError::Wall => "hit a wall",
+            StepError::Snake => "hit another snake",
+            StepError::HeadOn => "hit another snake head-on",
+        };
+        f.write_str(what)
+    }
 }
 
+#[derive(Debug, PartialEq)]
 pub enum StepOk {
     Nothing,
     AteFood,
     AteMystery,
 }
 
+/// A turn relative to the current direction of the snake head
+#[derive(Clone, Debug, PartialEq)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Direction {
     Up,
@@ -34,9 +55,17 @@ pub enum Direction {
     Right,
 }
 
-const SNAKE_INIT_X: u32 = FIELD_SIZE / 2;
-const SNAKE_INIT_Y: u32 = FIELD_SIZE / 2;
-const SNAKE_INIT_DIR: Direction = Direction::Up;
+impl Direction {
+    /// Where the head looks after the turn
+    pub fn turned(&self, turn: &Turn) -> Direction {
+        match (self, turn) {
+            (Direction::Down, Turn::Left) | (Direction::Up, Turn::Right) => Direction::Right,
+            (Direction::Right, Turn::Left) | (Direction::Left, Turn::Right) => Direction::Up,
+            (Direction::Up, Turn::Left) | (Direction::Down, Turn::Right) => Direction::Left,
+            (Direction::Left, Turn::Left) | (Direction::Right, Turn::Right) => Direction::Down,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Coords {
@@ -59,219 +88,448 @@ pub enum Thing {
     Lean,
     Mystery,
     Obstacle,
+    Wall,
 }
 
-#[derive(Clone, Debug)]
+/// How many things of every kind have appeared on the field since the game started
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Spawned {
+    pub food: u32,
+    pub lean: u32,
+    pub fat: u32,
+    pub obstacle: u32,
+    pub mystery: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct ThingInField {
     pub what: Thing,
     pub picture_index: usize,
-    // coordinates are from 1 to FIELD_SIZE
+    // coordinates are from 1 to the field width and height
     pub x: u32,
     pub y: u32,
     lifetime: Option<u32>,
 }
 
-pub struct World {
-    pub snake: Vec<SnakeCell>,
-    // what is it, index of the corresponding picture, coordinates, possible lifetime
-    pub things: Vec<ThingInField>,
+/// A snake on the field, with the score of its player
+#[derive(Debug)]
+pub struct Snake {
+    // from the head to the tail
+    pub cells: Vec<SnakeCell>,
     pub score: u32,
+    // why the snake is out of the game, if it is
+    pub dead: Option<StepError>,
     grow: u32, // grow for this amount of turns; 0 means do not grow
     eaten_food: u32,
 }
 
+impl Snake {
+    pub fn head(&self) -> &SnakeCell {
+        &self.cells[0]
+    }
+    /// Whether the snake grows on the next step, so that its tail stays where it is
+    pub fn growing(&self) -> bool {
+        self.grow > 0
+    }
+
+    pub fn turn(&mut self, turn: &Turn) {
+        self.cells[0].dir = self.cells[0].dir.turned(turn);
+    }
+
+    /// Point the snake head in the given direction, if it is one turn away from where the head
+    /// looks now and it does not lead back into the neck. Returns the turn made, if any.
+    pub fn set_direction(&mut self, dir: Direction) -> Option<Turn> {
+        let head = &self.cells[0];
+        let back = match head.prev_dir {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        };
+        if dir == back {
+            return None;
+        }
+        let turn = match (&head.dir, &dir) {
+            (Direction::Up, Direction::Left)
+            | (Direction::Left, Direction::Down)
+            | (Direction::Down, Direction::Right)
+            | (Direction::Right, Direction::Up) => Turn::Left,
+            (Direction::Up, Direction::Right)
+            | (Direction::Right, Direction::Down)
+            | (Direction::Down, Direction::Left)
+            | (Direction::Left, Direction::Up) => Turn::Right,
+            // already looking there, or it takes two turns
+            _ => return None,
+        };
+        self.turn(&turn);
+        Some(turn)
+    }
+
+    fn occupies(&self, x: u32, y: u32) -> bool {
+        self.cells
+            .iter()
+            .any(|s| s.coords.x == x && s.coords.y == y)
+    }
+}
+
+pub struct World {
+    // one snake per player; a single-player game has only the first one
+    pub snakes: Vec<Snake>,
+    // what is it, index of the corresponding picture, coordinates, possible lifetime
+    pub things: Vec<ThingInField>,
+    width: u32,
+    height: u32,
+    level_score: u32, // the score at the start of the current level
+    target: Option<u32>,
+    tick: u64, // the number of steps made
+    spawned: Spawned,
+    config: GameConfig,
+    seed: u64,
+    // all the randomness of the game comes from here, so the same seed gives the same game
+    rng: ChaCha8Rng,
+}
+
 impl World {
+    /// Create a world with the default configuration and a random seed.
     pub fn init() -> Self {
+        Self::with_seed(rand::thread_rng().gen())
+    }
+    /// Create a world with the default configuration on the open field.
+    pub fn with_seed(seed: u64) -> Self {
+        let config = GameConfig::default();
+        let level = Level::open(config.width(), config.field_height, None);
+        Self::new(config, &level, seed)
+    }
+    /// Create a world which always plays out the same way for the same configuration, level,
+    /// seed and input. There is a snake for every snake on the level. The configuration is
+    /// expected to be validated.
+    pub fn new(config: GameConfig, level: &Level, seed: u64) -> Self {
         let mut w = World {
-            snake: vec![
-                SnakeCell {
-                    dir: SNAKE_INIT_DIR,
-                    prev_dir: SNAKE_INIT_DIR,
-                    coords: Coords {
-                        x: SNAKE_INIT_X,
-                        y: SNAKE_INIT_Y,
-                    },
-                    even: false,
-                },
-                SnakeCell {
-                    dir: SNAKE_INIT_DIR,
-                    prev_dir: SNAKE_INIT_DIR,
-                    coords: Coords {
-                        x: SNAKE_INIT_X,
-                        y: SNAKE_INIT_Y + 1,
-                    },
-                    even: true,
-                },
-                SnakeCell {
-                    dir: SNAKE_INIT_DIR,
-                    prev_dir: SNAKE_INIT_DIR,
-                    coords: Coords {
-                        x: SNAKE_INIT_X,
-                        y: SNAKE_INIT_Y + 2,
-                    },
-                    even: false,
-                },
-            ],
+            snakes: vec![],
             things: vec![],
-            grow: 0,
-            score: 0,
-            eaten_food: 0,
+            width: 0,
+            height: 0,
+            level_score: 0,
+            target: None,
+            tick: 0,
+            spawned: Spawned::default(),
+            config,
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
         };
-        w.add_food();
+        w.load_level(level);
         w
     }
-    fn empty_spot(&self) -> (u32, u32) {
-        let mut rng = rand::thread_rng();
-        'looking: loop {
-            let x = rng.gen_range(0..FIELD_SIZE) + 1;
-            let y = rng.gen_range(0..FIELD_SIZE) + 1;
-            if self
-                .snake
-                .iter()
-                .any(|s| s.coords.x == x && s.coords.y == y)
-            {
-                continue 'looking;
+    /// Start the given level, keeping the scores.
+    pub fn load_level(&mut self, level: &Level) {
+        self.width = level.width;
+        self.height = level.height;
+        self.level_score = self.score();
+        self.target = level.target;
+        // the players keep their scores, and the food they have eaten counts for lean food
+        let kept: Vec<(u32, u32)> = self
+            .snakes
+            .iter()
+            .map(|s| (s.score, s.eaten_food))
+            .collect();
+        self.snakes = level
+            .spawns
+            .iter()
+            .enumerate()
+            .map(|(idx, spawn)| {
+                let (score, eaten_food) = kept.get(idx).copied().unwrap_or_default();
+                Snake {
+                    cells: spawn
+                        .cells()
+                        .enumerate()
+                        .map(|(i, (x, y))| SnakeCell {
+                            dir: spawn.direction.clone(),
+                            prev_dir: spawn.direction.clone(),
+                            coords: Coords {
+                                x: x as u32,
+                                y: y as u32,
+                            },
+                            even: i % 2 == 1,
+                        })
+                        .collect(),
+                    score,
+                    dead: None,
+                    grow: 0,
+                    eaten_food,
+                }
+            })
+            .collect();
+        self.things = level
+            .walls
+            .iter()
+            .map(|&(x, y)| ThingInField {
+                what: Thing::Wall,
+                picture_index: 0,
+                x,
+                y,
+                lifetime: None,
+            })
+            .collect();
+        self.add_food(self.most_eaten_food());
+    }
+    /// Whether the target score of the current level is reached
+    pub fn level_complete(&self) -> bool {
+        self.target
+            .is_some_and(|target| self.score() - self.level_score >= target)
+    }
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+    pub fn spawned(&self) -> &Spawned {
+        &self.spawned
+    }
+    /// The first snake, the only one in a single-player game
+    pub fn snake(&self) -> &Snake {
+        &self.snakes[0]
+    }
+    pub fn snake_mut(&mut self) -> &mut Snake {
+        &mut self.snakes[0]
+    }
+    /// The score of all the players together, which is the player's score in a single-player game
+    pub fn score(&self) -> u32 {
+        self.snakes.iter().map(|s| s.score).sum()
+    }
+    /// A random spot for a new thing, or None if there is no room left on the field
+    fn empty_spot(&mut self) -> Option<(u32, u32)> {
+        let mut misses = 0;
+        loop {
+            let x = self.rng.gen_range(0..self.width) + 1;
+            let y = self.rng.gen_range(0..self.height) + 1;
+            if self.is_empty_spot(x, y) {
+                return Some((x, y));
             }
-            // prevent things appearing next to snake
-            let hx = self.snake[0].coords.x;
-            let hy = self.snake[0].coords.y;
-            if x < hx + 3 && y < hy + 3 && x > hx.saturating_sub(3) && y > hy.saturating_sub(3) {
-                continue 'looking;
+            // a long snake may fill the field; the check does not change the random numbers
+            misses += 1;
+            if misses == self.width * self.height
+                && !(1..=self.width).any(|x| (1..=self.height).any(|y| self.is_empty_spot(x, y)))
+            {
+                return None;
             }
-            if self.things.iter().any(|t| t.x == x && t.y == y) {
-                continue 'looking;
+        }
+    }
+    fn is_empty_spot(&self, x: u32, y: u32) -> bool {
+        if self.snakes.iter().any(|s| s.occupies(x, y)) {
+            return false;
+        }
+        // prevent things appearing next to the snakes
+        for s in self.snakes.iter().filter(|s| s.dead.is_none()) {
+            let (hx, hy) = (s.cells[0].coords.x, s.cells[0].coords.y);
+            if self.distance(x, hx, self.width) < 3 && self.distance(y, hy, self.height) < 3 {
+                return false;
             }
-            return (x, y);
+        }
+        !self.things.iter().any(|t| t.x == x && t.y == y)
+    }
+    /// The distance between two coordinates along the axis of the given size, going across the
+    /// border if the field wraps
+    fn distance(&self, a: u32, b: u32, size: u32) -> u32 {
+        let d = a.abs_diff(b);
+        if self.config.wrap {
+            min(d, size - d)
+        } else {
+            d
+        }
+    }
+    /// The cell the head moves to, or None if it is out of the field
+    fn next_cell(&self, head: &SnakeCell) -> Option<(u32, u32)> {
+        let (x, y) = (head.coords.x, head.coords.y);
+        let (width, height) = (self.width, self.height);
+        let wrap = self.config.wrap;
+        match head.dir {
+            Direction::Up if y > 1 => Some((x, y - 1)),
+            Direction::Down if y < height => Some((x, y + 1)),
+            Direction::Left if x > 1 => Some((x - 1, y)),
+            Direction::Right if x < width => Some((x + 1, y)),
+            _ if !wrap => None,
+            Direction::Up => Some((x, height)),
+            Direction::Down => Some((x, 1)),
+            Direction::Left => Some((width, y)),
+            Direction::Right => Some((1, y)),
         }
     }
+    /// Move the snakes one cell; the result is the first snake's, the only one in a
+    /// single-player game
     pub fn step(&mut self) -> Result<StepOk, StepError> {
-        let c = &self.snake[0].coords;
-        let mut next_x = c.x;
-        let mut next_y = c.y;
-        let mut step_ok = StepOk::Nothing;
-
-        match self.snake[0].dir {
-            Direction::Up => {
-                if next_y > 1 {
-                    next_y -= 1;
-                } else {
-                    return Err(StepError::OutOfField);
+        self.step_all().swap_remove(0)
+    }
+    /// Move all the snakes which are still in the game one cell at the same time. Returns what
+    /// happened to every snake; the ones out of the game get the reason again.
+    ///
+    /// A snake dies when its head hits a snake's body, its own included. When two heads get to
+    /// the same cell, both snakes die. A snake which dies stays where it was before the step.
+    pub fn step_all(&mut self) -> Vec<Result<StepOk, StepError>> {
+        self.tick += 1;
+        let mut results: Vec<Result<StepOk, StepError>> = self
+            .snakes
+            .iter()
+            .map(|s| s.dead.map_or(Ok(StepOk::Nothing), Err))
+            .collect();
+
+        let mut next: Vec<Option<(u32, u32)>> = vec![None; self.snakes.len()];
+        for (idx, s) in self.snakes.iter().enumerate() {
+            if results[idx].is_ok() {
+                next[idx] = self.next_cell(s.head());
+                if next[idx].is_none() {
+                    results[idx] = Err(StepError::OutOfField);
                 }
             }
-            Direction::Down => {
-                if next_y < FIELD_SIZE {
-                    next_y += 1
-                } else {
-                    return Err(StepError::OutOfField);
-                }
+        }
+
+        // Start by moving the snakes so when we create new things their positions are updated
+
+        // Maybe shrink the snakes; a head can go where a tail was. The tails are kept for the
+        // snakes which die, so that they stay on the field as they were.
+        let mut tails: Vec<Option<SnakeCell>> = Vec::with_capacity(self.snakes.len());
+        for (idx, s) in self.snakes.iter_mut().enumerate() {
+            if next[idx].is_none() || s.grow > 0 {
+                tails.push(None);
+                continue;
             }
-            Direction::Left => {
-                if next_x > 1 {
-                    next_x -= 1;
+            tails.push(s.cells.pop());
+        }
+        // Check if the heads hit a snake
+        for (idx, cell) in next.iter().enumerate() {
+            let Some((x, y)) = *cell else {
+                continue;
+            };
+            if next
+                .iter()
+                .enumerate()
+                .any(|(other, c)| other != idx && *c == Some((x, y)))
+            {
+                results[idx] = Err(StepError::HeadOn);
+            } else if let Some(hit) = self.snakes.iter().position(|s| s.occupies(x, y)) {
+                results[idx] = Err(if hit == idx {
+                    StepError::SelfHit
                 } else {
-                    return Err(StepError::OutOfField);
-                }
+                    StepError::Snake
+                });
             }
-            Direction::Right => {
-                if next_x < FIELD_SIZE {
-                    next_x += 1
-                } else {
-                    return Err(StepError::OutOfField);
-                }
+        }
+        for (idx, s) in self.snakes.iter_mut().enumerate() {
+            let Some((x, y)) = next[idx] else {
+                continue;
+            };
+            if results[idx].is_err() {
+                next[idx] = None;
+                s.cells.extend(tails[idx].take());
+                continue;
             }
-        };
-
-        // Start by moving a snake so when we create new things snake position is updated
-
-        // Maybe shrink snake
-        if self.grow == 0 {
-            self.snake.pop();
-        } else {
-            assert!(
-                self.grow > 0,
-                "Programming error: the number of turns snake grows cannot be negative"
+            if tails[idx].is_none() {
+                s.grow -= 1;
+            }
+            let dir = s.cells[0].dir.clone();
+            let prev_dir = s.cells[0].dir.clone();
+            let even = !s.cells[0].even;
+            s.cells.insert(
+                0,
+                SnakeCell {
+                    dir,
+                    prev_dir,
+                    even,
+                    coords: Coords { x, y },
+                },
             );
-            self.grow -= 1;
         }
-        // Check if we hit snake
-        if self
-            .snake
-            .iter()
-            .any(|s| s.coords.x == next_x && s.coords.y == next_y)
-        {
-            return Err(StepError::SelfHit);
+        if self.mark_dead(&results) {
+            return results;
         }
-        let dir = self.snake[0].dir.clone();
-        let prev_dir = self.snake[0].dir.clone();
-        let even = !self.snake[0].even;
-        self.snake.insert(
-            0,
-            SnakeCell {
-                dir,
-                prev_dir,
-                even,
-                coords: Coords {
-                    x: next_x,
-                    y: next_y,
-                },
-            },
-        );
 
         // Now go through things, check if we hit something, update lifetimes
         let mut deleted = 0;
         for (idx, thing) in self.things.clone().iter_mut().enumerate() {
-            match thing.lifetime {
-                Some(0) =>
+            let hit_by = next.iter().position(|c| *c == Some((thing.x, thing.y)));
+            match (thing.lifetime, hit_by) {
+                (Some(0), _) =>
                 // the thing is expired
                 {
                     if thing.what == Thing::Food || thing.what == Thing::Lean {
-                        self.add_food();
+                        self.add_food(self.most_eaten_food());
                     }
                     self.things.remove(idx - deleted);
                     deleted += 1;
                     continue; // if the thing is expired, do not check if we hit it
                 }
-                _ if thing.x == next_x && thing.y == next_y => {
-                    // we hit it
-                    self.things.remove(idx - deleted);
-                    deleted += 1;
+                (_, Some(s)) => {
+                    // we hit it; what kills the snake stays on the field
+                    let deadly = matches!(thing.what, Thing::Obstacle | Thing::Wall);
+                    if !deadly {
+                        self.things.remove(idx - deleted);
+                        deleted += 1;
+                    }
+                    let snake = &mut self.snakes[s];
                     match thing.what {
-                        Thing::Obstacle => {
-                            return Err(StepError::Obstacle);
+                        Thing::Obstacle | Thing::Wall => {
+                            results[s] = Err(if thing.what == Thing::Obstacle {
+                                StepError::Obstacle
+                            } else {
+                                StepError::Wall
+                            });
+                            next[s] = None;
+                            // the snake stays where it was, as the snakes which hit a snake do
+                            snake.cells.remove(0);
+                            match tails[s].take() {
+                                Some(tail) => snake.cells.push(tail),
+                                None => snake.grow += 1,
+                            }
+                            if let Some(n) = thing.lifetime {
+                                self.things[idx - deleted].lifetime = Some(n - 1);
+                            }
+                            if self.mark_dead(&results) {
+                                return results;
+                            }
                         }
                         Thing::Food => {
-                            self.eaten_food += 1;
-                            self.score += 1;
-                            self.grow += 3;
-                            step_ok = StepOk::AteFood;
-                            self.add_food();
+                            snake.eaten_food += 1;
+                            snake.score += 1;
+                            snake.grow += self.config.food_grow_snake;
+                            results[s] = Ok(StepOk::AteFood);
+                            let eaten_food = snake.eaten_food;
+                            self.add_food(eaten_food);
                         }
                         Thing::Fat => {
-                            self.eaten_food += 1;
-                            self.score += 1;
-                            self.grow += FAT_GROW_SNAKE;
-                            step_ok = StepOk::AteFood;
-                            self.add_food();
+                            snake.eaten_food += 1;
+                            snake.score += 1;
+                            snake.grow += self.config.fat_grow_snake;
+                            results[s] = Ok(StepOk::AteFood);
+                            let eaten_food = snake.eaten_food;
+                            self.add_food(eaten_food);
                         }
                         Thing::Lean => {
-                            self.eaten_food = 0;
-                            self.score += 1;
-                            step_ok = StepOk::AteFood;
-                            self.add_food();
+                            snake.eaten_food = 0;
+                            snake.score += 1;
+                            results[s] = Ok(StepOk::AteFood);
+                            self.add_food(0);
                         }
                         Thing::Mystery => {
-                            let mut rng = rand::thread_rng();
-                            if rng.sample(Uniform::new(0.0, 1.0)) < 0.5 {
-                                self.score += MYSTERY_SCORE;
-                                self.eaten_food += 1;
+                            if self.rng.sample(Uniform::new(0.0, 1.0)) < 0.5 {
+                                snake.score += self.config.mystery_score;
+                                snake.eaten_food += 1;
                             } else {
-                                self.grow += MYSTERY_GROW_SNAKE;
+                                snake.grow += self.config.mystery_grow_snake;
                             }
-                            step_ok = StepOk::AteMystery;
+                            results[s] = Ok(StepOk::AteMystery);
                         }
                     }
                 }
-                Some(n) => {
+                (Some(n), None) => {
                     // We did not hit it
                     self.things.remove(idx - deleted);
                     deleted += 1;
@@ -280,7 +538,6 @@ impl World {
                         ..thing.clone()
                     });
                     continue;
-                    // we hit it
                 }
                 _ => {}
             }
@@ -289,46 +546,63 @@ impl World {
         self.maybe_add_obstacle();
         self.maybe_add_mystery();
 
-        Ok(step_ok)
+        results
+    }
+    /// The food eaten by the snake which has eaten the most, for new food which nobody has eaten
+    fn most_eaten_food(&self) -> u32 {
+        self.snakes.iter().map(|s| s.eaten_food).max().unwrap_or(0)
+    }
+    /// Take the snakes which have just died out of the game; true if none is left
+    fn mark_dead(&mut self, results: &[Result<StepOk, StepError>]) -> bool {
+        for (s, result) in self.snakes.iter_mut().zip(results) {
+            if let Err(e) = result {
+                s.dead = Some(*e);
+            }
+        }
+        self.snakes.iter().all(|s| s.dead.is_some())
+    }
+
+    /// Turn the first snake, the only one in a single-player game
+    pub fn turn(&mut self, turn: &Turn) {
+        self.snakes[0].turn(turn);
+    }
+
+    /// Point the head of the first snake in the given direction, see `Snake::set_direction`
+    pub fn set_direction(&mut self, dir: Direction) -> Option<Turn> {
+        self.snakes[0].set_direction(dir)
     }
 
     pub fn turn_left(&mut self) {
-        let dir = match self.snake[0].dir {
-            Direction::Down => Direction::Right,
-            Direction::Right => Direction::Up,
-            Direction::Up => Direction::Left,
-            Direction::Left => Direction::Down,
-        };
-        self.snake[0].dir = dir;
+        self.turn(&Turn::Left);
     }
 
     pub fn turn_right(&mut self) {
-        let dir = match self.snake[0].dir {
-            Direction::Down => Direction::Left,
-            Direction::Right => Direction::Down,
-            Direction::Up => Direction::Right,
-            Direction::Left => Direction::Up,
-        };
-        self.snake[0].dir = dir;
+        self.turn(&Turn::Right);
     }
 
-    fn add_food(&mut self) {
-        let (x, y) = self.empty_spot();
-        let mut rng = rand::thread_rng();
-        if self.eaten_food >= LEAN_AFTER_FOOD && rng.sample(Uniform::new(0.0, 1.0)) < LEAN_P {
+    /// Put new food on the field; lean food may appear after the given amount of food eaten
+    fn add_food(&mut self, eaten_food: u32) {
+        let Some((x, y)) = self.empty_spot() else {
+            return;
+        };
+        if eaten_food >= self.config.lean_after_food
+            && self.rng.sample(Uniform::new(0.0, 1.0)) < self.config.lean_p
+        {
+            self.spawned.lean += 1;
             self.things.push(ThingInField {
                 what: Thing::Lean,
-                picture_index: rng.gen_range(0..3),
+                picture_index: self.rng.gen_range(0..3),
                 x,
                 y,
-                lifetime: Some(LEAN_LIFETIME),
+                lifetime: Some(self.config.lean_lifetime),
             });
             return;
         }
-        if rng.sample(Uniform::new(0.0, 1.0)) < FAT_P {
+        if self.rng.sample(Uniform::new(0.0, 1.0)) < self.config.fat_p {
+            self.spawned.fat += 1;
             self.things.push(ThingInField {
                 what: Thing::Fat,
-                picture_index: rng.gen_range(0..3),
+                picture_index: self.rng.gen_range(0..3),
                 x,
                 y,
                 lifetime: None,
@@ -336,15 +610,19 @@ impl World {
             return;
         }
 
-        let lifetime = if x == 1 || y == 1 || x == FIELD_SIZE || y == FIELD_SIZE {
+        let (width, height) = (self.width, self.height);
+        // food next to the wall is hard to collect, so it stays; without the wall it is as
+        // easy to get as any other food
+        let on_border = x == 1 || y == 1 || x == width || y == height;
+        let lifetime = if on_border && !self.config.wrap {
             None
         } else {
-            Some(FOOD_LIFETIME)
+            Some(self.config.food_lifetime)
         };
-        let mut rng = rand::thread_rng();
+        self.spawned.food += 1;
         self.things.push(ThingInField {
             what: Thing::Food,
-            picture_index: rng.gen_range(0..3),
+            picture_index: self.rng.gen_range(0..3),
             x,
             y,
             lifetime,
@@ -352,32 +630,214 @@ impl World {
     }
 
     fn maybe_add_obstacle(&mut self) {
-        let mut rng = rand::thread_rng();
-        if rng.sample(Uniform::new(0.0, 1.0)) > OBSTACLE_P {
+        if self.rng.sample(Uniform::new(0.0, 1.0)) > self.config.obstacle_p {
             return;
         }
-        let (x, y) = self.empty_spot();
+        let Some((x, y)) = self.empty_spot() else {
+            return;
+        };
+        self.spawned.obstacle += 1;
         self.things.push(ThingInField {
             what: Thing::Obstacle,
-            picture_index: rng.gen_range(0..3),
+            picture_index: self.rng.gen_range(0..3),
             x,
             y,
-            lifetime: Some(OBSTACLE_LIFETIME),
+            lifetime: Some(self.config.obstacle_lifetime),
         });
     }
 
     fn maybe_add_mystery(&mut self) {
-        let mut rng = rand::thread_rng();
-        if rng.sample(Uniform::new(0.0, 1.0)) > MYSTERY_P {
+        if self.rng.sample(Uniform::new(0.0, 1.0)) > self.config.mystery_p {
             return;
         }
-        let (x, y) = self.empty_spot();
+        let Some((x, y)) = self.empty_spot() else {
+            return;
+        };
+        self.spawned.mystery += 1;
         self.things.push(ThingInField {
             what: Thing::Mystery,
-            picture_index: rng.gen_range(0..4),
+            picture_index: self.rng.gen_range(0..4),
             x,
             y,
-            lifetime: Some(MYSTERY_LIFETIME),
+            lifetime: Some(self.config.mystery_lifetime),
         });
     }
 }
+
+/// A world on the level where nothing appears by chance but the food, for the tests
+#[cfg(test)]
+pub(crate) fn test_world(level: &Level, seed: u64) -> World {
+    let config = GameConfig {
+        field_width: Some(level.width),
+        field_height: level.height,
+        obstacle_p: 0.0,
+        mystery_p: 0.0,
+        ..GameConfig::default()
+    };
+    World::new(config, level, seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Spawn;
+
+    /// The open field with the given snakes
+    fn arena(spawns: Vec<Spawn>) -> Level {
+        Level {
+            spawns,
+            ..Level::open(20, 20, None)
+        }
+    }
+
+    #[test]
+    fn the_seed_decides_where_things_appear() {
+        let play = |seed: u64| {
+            let mut w = World::with_seed(seed);
+            // go round in circles, until something gets in the way
+            for tick in 0..200 {
+                if tick % 5 == 4 {
+                    w.turn_left();
+                }
+                if w.step().is_err() {
+                    break;
+                }
+            }
+            (w.tick(), w.things, w.spawned)
+        };
+        assert_eq!(play(7), play(7));
+        assert_ne!(play(7).1, play(8).1);
+    }
+
+    #[test]
+    fn heads_meet() {
+        let mut w = test_world(
+            &arena(vec![
+                Spawn::new(5, 10, Direction::Right),
+                Spawn::new(7, 10, Direction::Left),
+            ]),
+            1,
+        );
+        let results = w.step_all();
+        assert!(matches!(
+            results[..],
+            [Err(StepError::HeadOn), Err(StepError::HeadOn)]
+        ));
+        assert!(w.snakes.iter().all(|s| s.dead == Some(StepError::HeadOn)));
+    }
+
+    #[test]
+    fn dead_snakes_keep_their_tails() {
+        let mut w = test_world(
+            &arena(vec![
+                Spawn::new(5, 10, Direction::Right),
+                Spawn::new(7, 10, Direction::Left),
+            ]),
+            1,
+        );
+        w.step_all();
+        assert!(w.snakes.iter().all(|s| s.cells.len() == 3));
+        // the world as it ended can be drawn
+        let sprites = crate::render::foreground(&w);
+        assert!(sprites.len() >= 6);
+    }
+
+    #[test]
+    fn head_hits_the_other_body() {
+        let mut w = test_world(
+            &arena(vec![
+                Spawn::new(5, 10, Direction::Right),
+                Spawn::new(6, 12, Direction::Up),
+            ]),
+            1,
+        );
+        assert!(w.step_all().iter().all(|r| r.is_ok()));
+        // the second head goes where the first snake's neck is
+        let results = w.step_all();
+        assert!(matches!(results[..], [Ok(_), Err(StepError::Snake)]));
+        // the snake out of the game stays where it died
+        let results = w.step_all();
+        assert!(matches!(results[..], [Ok(_), Err(StepError::Snake)]));
+        assert_eq!(w.snakes[1].head().coords.y, 11);
+    }
+
+    #[test]
+    fn a_snake_which_hits_a_wall_stays_before_it() {
+        let level = Level {
+            walls: vec![(6, 10)],
+            ..arena(vec![Spawn::new(5, 10, Direction::Right)])
+        };
+        let mut w = test_world(&level, 1);
+        assert!(matches!(w.step(), Err(StepError::Wall)));
+        assert!(w
+            .things
+            .iter()
+            .any(|t| t.what == Thing::Wall && (t.x, t.y) == (6, 10)));
+        let cells: Vec<(u32, u32)> = w
+            .snake()
+            .cells
+            .iter()
+            .map(|c| (c.coords.x, c.coords.y))
+            .collect();
+        assert_eq!(cells, [(5, 10), (4, 10), (3, 10)]);
+    }
+
+    /// A snake at every border, going across it, and where its head gets with the wrap
+    fn across_the_borders() -> Vec<(Spawn, (u32, u32))> {
+        vec![
+            (Spawn::new(20, 5, Direction::Right), (1, 5)),
+            (Spawn::new(1, 10, Direction::Left), (20, 10)),
+            (Spawn::new(15, 1, Direction::Up), (15, 20)),
+            (Spawn::new(5, 20, Direction::Down), (5, 1)),
+        ]
+    }
+
+    fn border_world(spawns: Vec<Spawn>, wrap: bool) -> World {
+        let mut w = test_world(&arena(spawns), 1);
+        w.config.wrap = wrap;
+        w
+    }
+
+    fn head(snake: &Snake) -> (u32, u32) {
+        (snake.head().coords.x, snake.head().coords.y)
+    }
+
+    #[test]
+    fn a_snake_wraps_around_every_border() {
+        for (spawn, there) in across_the_borders() {
+            let mut w = border_world(vec![spawn.clone()], true);
+            assert!(w.step().is_ok(), "{:?}", spawn);
+            assert_eq!(head(w.snake()), there, "{:?}", spawn);
+            assert_eq!(w.snake().cells.len(), 3);
+        }
+        let (spawns, there): (Vec<Spawn>, Vec<(u32, u32)>) =
+            across_the_borders().into_iter().unzip();
+        let mut w = border_world(spawns, true);
+        assert!(w.step_all().iter().all(|r| r.is_ok()));
+        let heads: Vec<(u32, u32)> = w.snakes.iter().map(head).collect();
+        assert_eq!(heads, there);
+    }
+
+    #[test]
+    fn without_the_wrap_a_snake_dies_at_every_border() {
+        for (spawn, _) in across_the_borders() {
+            let mut w = border_world(vec![spawn.clone()], false);
+            assert!(
+                matches!(w.step(), Err(StepError::OutOfField)),
+                "{:?}",
+                spawn
+            );
+            assert_eq!(head(w.snake()), (spawn.x, spawn.y));
+        }
+        let spawns: Vec<Spawn> = across_the_borders().into_iter().map(|(s, _)| s).collect();
+        let mut w = border_world(spawns, false);
+        assert!(w
+            .step_all()
+            .iter()
+            .all(|r| matches!(r, Err(StepError::OutOfField))));
+        assert!(w
+            .snakes
+            .iter()
+            .all(|s| s.dead == Some(StepError::OutOfField)));
+    }
+}